@@ -1,170 +1,612 @@
+// Generates the client enums, the tag maps, the display names, the client metadata and the
+// version style assignment from registry.toml. See the top of registry.toml for the format.
+
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::fs::File;
+use std::fmt::Write as _;
+use std::fs::{self, File};
 use std::io::{BufWriter, Write};
 use std::path::Path;
 
-struct TagMap {
-    filename: &'static str,
-    definition: &'static str,
-    phf: String,
-}
-
-fn codegen_tags_azureus() -> TagMap {
-    let filename = "codegen_tags_azureus.rs";
-    let definition = "static TAGS: phf::Map<&'static [u8], Azureus> =";
-    let tags: Vec<(&[u8], &str)> = vec![
-        (b"A~", "Azureus::AresThreeDigits"),
-        (b"AG", "Azureus::AresThreeDigits"),
-        (b"AN", "Azureus::AresFourDigits"),
-        (b"AR", "Azureus::AresFourDigits"),
-        (b"AV", "Azureus::Avicora"),
-        (b"AX", "Azureus::BitPump"),
-        (b"AT", "Azureus::Artemis"),
-        (b"AZ", "Azureus::Vuze"),
-        (b"BB", "Azureus::BitBuddy"),
-        (b"BC", "Azureus::BitComet"),
-        (b"BE", "Azureus::BitTorrentSDK"),
-        (b"BF", "Azureus::BitFlu"),
-        (b"BG", "Azureus::Btg"),
-        (b"BI", "Azureus::BiglyBt"),
-        (b"bk", "Azureus::BitKitten"),
-        (b"BR", "Azureus::BitRocket"),
-        (b"BS", "Azureus::BTSlave"),
-        (b"BT", "Azureus::BitTorrent"),
-        (b"BW", "Azureus::BitWombat"),
-        (b"BX", "Azureus::BittorrentX"),
-        (b"CB", "Azureus::ShareazaPlus"),
-        (b"CD", "Azureus::EnhancedCTorrent"),
-        (b"CT", "Azureus::CTorrent"),
-        (b"DP", "Azureus::PropogateDataClient"),
-        (b"DE", "Azureus::Deluge"),
-        (b"EB", "Azureus::EBit"),
-        (b"ES", "Azureus::ElectricSheep"),
-        (b"FC", "Azureus::FileCroc"),
-        (b"FD", "Azureus::FreeDownloadManager"),
-        (b"FG", "Azureus::FlashGet"),
-        (b"FL", "Azureus::Folx"),
-        (b"FX", "Azureus::FreeboxBitTorrent"),
-        (b"FT", "Azureus::FoxTorrentRedSwoosh"),
-        (b"GR", "Azureus::GetRight"),
-        (b"GS", "Azureus::GSTorrent"),
-        (b"HL", "Azureus::Halite"),
-        (b"HN", "Azureus::Hydranode"),
-        (b"KG", "Azureus::KGet"),
-        (b"KT", "Azureus::KTorrent"),
-        (b"LC", "Azureus::LeechCraft"),
-        (b"LH", "Azureus::LhAbc"),
-        (b"LK", "Azureus::Linkage"),
-        (b"LP", "Azureus::Lphant"),
-        (b"LT", "Azureus::LibtorrentRasterbar"),
-        (b"lt", "Azureus::LibTorrentRakshasa"),
-        (b"LW", "Azureus::LimeWire"),
-        (b"MG", "Azureus::MediaGet"),
-        (b"MO", "Azureus::MonoTorrent"),
-        (b"MP", "Azureus::MooPolice"),
-        (b"MR", "Azureus::Miro"),
-        (b"MT", "Azureus::MoonlightTorrent"),
-        (b"NE", "Azureus::BTNextEvolution"),
-        (b"NX", "Azureus::NetTransport"),
-        (b"OS", "Azureus::OneSwarm"),
-        (b"OT", "Azureus::OmegaTorrent"),
-        (b"PC", "Azureus::CacheLogic"),
-        (b"PT", "Azureus::PopcornTime"),
-        (b"PD", "Azureus::Pando"),
-        (b"PE", "Azureus::PeerProject"),
-        (b"pX", "Azureus::PHoeniX"),
-        (b"qB", "Azureus::QBittorrent"),
-        (b"QD", "Azureus::QqDownload"),
-        (b"RM", "Azureus::RumTorrent"),
-        (b"RT", "Azureus::Retriever"),
-        (b"RZ", "Azureus::RezTorrent"),
-        (b"S~", "Azureus::ShareazaAlphaBeta"),
-        (b"SB", "Azureus::SwiftBit"),
-        (b"SD", "Azureus::Xunlei"),
-        (b"SG", "Azureus::GSTorrent"),
-        (b"SN", "Azureus::ShareNET"),
-        (b"SP", "Azureus::BitSpirit"),
-        (b"SS", "Azureus::SwarmScope"),
-        (b"ST", "Azureus::SymTorrent"),
-        (b"st", "Azureus::SharkTorrent"),
-        (b"SZ", "Azureus::Shareaza"),
-        (b"TG", "Azureus::TorrentGO"),
-        (b"TN", "Azureus::TorrentDotNET"),
-        (b"TR", "Azureus::Transmission"),
-        (b"TS", "Azureus::TorrentStorm"),
-        (b"TT", "Azureus::TuoTu"),
-        (b"UL", "Azureus::ULeecher"),
-        (b"UE", "Azureus::UTorrentEmbedded"),
-        (b"UT", "Azureus::UTorrent"),
-        (b"UM", "Azureus::UTorrentMac"),
-        (b"UW", "Azureus::UTorrentWeb"),
-        (b"WD", "Azureus::WebTorrentDesktop"),
-        (b"WT", "Azureus::Bitlet"),
-        (b"WW", "Azureus::WebTorrent"),
-        (b"WY", "Azureus::FireTorrent"),
-        (b"VG", "Azureus::Vagaa"),
-        (b"XL", "Azureus::Xunlei"),
-        (b"XT", "Azureus::XanTorrent"),
-        (b"XF", "Azureus::Xfplay"),
-        (b"XX", "Azureus::XTorrent"),
-        (b"XC", "Azureus::XTorrent"),
-        (b"ZT", "Azureus::ZipTorrent"),
-        (b"7T", "Azureus::ATorrent"),
-        (b"ZO", "Azureus::Zona"),
-        (b"#@", "Azureus::InvalidPeerId"),
-    ];
-
-    TagMap {
-        filename,
-        definition,
-        phf: tags
-            .into_iter()
-            .fold(phf_codegen::Map::new(), |mut builder, (k, v)| {
-                builder.entry(k, v);
-                builder
-            })
-            .build()
-            .to_string(),
-    }
-}
-
-fn codegen_tags_shadow() -> TagMap {
-    let filename = "codegen_tags_shadow.rs";
-    let definition = "static TAGS: phf::Map<u8, Shadow> =";
-    let tags = vec![
-        (b'A', "Shadow::Abc"),
-        (b'O', "Shadow::OspreyPermaseed"),
-        (b'Q', "Shadow::BTQueue"),
-        (b'R', "Shadow::Tribler"),
-        (b'S', "Shadow::Shad0w"),
-        (b'T', "Shadow::BitTornado"),
-        (b'U', "Shadow::UPnPNat"),
-    ];
-
-    TagMap {
-        filename,
-        definition,
-        phf: tags
-            .into_iter()
-            .fold(phf_codegen::Map::new(), |mut builder, (k, v)| {
-                builder.entry(k, v);
-                builder
-            })
-            .build()
-            .to_string(),
+const REGISTRY: &str = "registry.toml";
+
+// clients that have their own parsers in client.rs and mainline.rs, so they don't
+// appear in any of the tables
+const HAND_PARSED: &[&str] = &[
+    "Mainline",
+    "QueenBee",
+    "AmazonAWSS3",
+    "BitComet",
+    "BitLord",
+    "BitSpirit",
+    "PossibleBitSpirit",
+];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Registry {
+    client: Vec<ClientEntry>,
+    azureus: Vec<AzureusEntry>,
+    shadow: Vec<ShadowEntry>,
+    substring: Vec<SubstringEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClientEntry {
+    variant: String,
+    id: String,
+    name: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    homepage: Option<String>,
+    maintained: Option<bool>,
+    first_release: Option<u16>,
+    last_release: Option<u16>,
+    #[serde(default)]
+    platforms: Vec<String>,
+    license: Option<String>,
+    engine: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AzureusEntry {
+    variant: String,
+    client: Option<String>,
+    tags: Vec<String>,
+    style: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShadowEntry {
+    variant: String,
+    client: Option<String>,
+    tag: String,
+}
+
+#[derive(Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum SubstringVersion {
+    None,
+    Unknown,
+    Fixed,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubstringEntry {
+    variant: String,
+    client: Option<String>,
+    rules: Vec<(String, usize)>,
+    version: SubstringVersion,
+    fixed: Option<String>,
+    numeric: Option<Vec<u8>>,
+    pre_release: Option<String>,
+}
+
+// the canonical client of a style-specific variant, defaults to the variant itself
+fn canonical<'a>(variant: &'a str, client: &'a Option<String>) -> &'a str {
+    client.as_deref().unwrap_or(variant)
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_alphanumeric())
+}
+
+// collects every problem in the registry, so that one build reports all of them
+#[derive(Default)]
+struct Validator {
+    errors: Vec<String>,
+}
+
+impl Validator {
+    fn check(&mut self, ok: bool, message: impl FnOnce() -> String) {
+        if !ok {
+            self.errors.push(message());
+        }
+    }
+
+    fn unique<'a>(&mut self, what: &str, values: impl IntoIterator<Item = &'a str>) {
+        let mut seen = HashSet::new();
+        for v in values {
+            self.check(seen.insert(v), || format!("duplicate {what} {v:?}"));
+        }
+    }
+
+    fn client<'a>(
+        &mut self,
+        (clients, referenced): (&HashSet<&str>, &mut HashSet<&'a str>),
+        style: &str,
+        variant: &'a str,
+        client: &'a Option<String>,
+    ) {
+        let client = canonical(variant, client);
+        self.check(clients.contains(client), || {
+            format!("{style} variant {variant:?} refers to an unknown client {client:?}")
+        });
+        self.check(is_identifier(variant), || {
+            format!("{style} variant {variant:?} isn't a valid identifier")
+        });
+        referenced.insert(client);
+    }
+
+    fn validate(mut self, registry: &Registry) {
+        let clients = registry
+            .client
+            .iter()
+            .map(|c| c.variant.as_str())
+            .collect::<HashSet<_>>();
+
+        self.unique(
+            "client variant",
+            registry.client.iter().map(|c| c.variant.as_str()),
+        );
+        self.unique("client id", registry.client.iter().map(|c| c.id.as_str()));
+        self.unique(
+            "client name or alias",
+            registry.client.iter().flat_map(|c| {
+                std::iter::once(c.name.as_str()).chain(c.aliases.iter().map(String::as_str))
+            }),
+        );
+        for c in &registry.client {
+            self.check(is_identifier(&c.variant), || {
+                format!("client variant {:?} isn't a valid identifier", c.variant)
+            });
+            self.check(
+                !c.id.is_empty()
+                    && c.id
+                        .bytes()
+                        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_'),
+                || {
+                    format!(
+                        "client id {:?} must be lowercase ASCII, digits or `_`",
+                        c.id
+                    )
+                },
+            );
+            self.check(!c.name.is_empty(), || {
+                format!("client {:?} has an empty name", c.variant)
+            });
+            self.metadata(registry, c);
+        }
+
+        let mut referenced = HAND_PARSED.iter().copied().collect::<HashSet<_>>();
+
+        self.unique(
+            "azureus variant",
+            registry.azureus.iter().map(|e| e.variant.as_str()),
+        );
+        self.unique(
+            "azureus tag",
+            registry
+                .azureus
+                .iter()
+                .flat_map(|e| e.tags.iter().map(String::as_str)),
+        );
+        for e in &registry.azureus {
+            self.client(
+                (&clients, &mut referenced),
+                "azureus",
+                &e.variant,
+                &e.client,
+            );
+            for tag in &e.tags {
+                self.check(tag.len() == 2 && tag.is_ascii(), || {
+                    format!("azureus tag {tag:?} must be two ASCII bytes")
+                });
+            }
+            // styles, pre-releases and platforms are written out as enum paths, so the
+            // compiler checks them against the enums, only the syntax is checked here
+            self.check(is_identifier(&e.style), || {
+                format!(
+                    "azureus variant {:?} has a style {:?} that isn't a valid identifier",
+                    e.variant, e.style
+                )
+            });
+        }
+
+        self.unique(
+            "shadow variant",
+            registry.shadow.iter().map(|e| e.variant.as_str()),
+        );
+        self.unique("shadow tag", registry.shadow.iter().map(|e| e.tag.as_str()));
+        for e in &registry.shadow {
+            self.client((&clients, &mut referenced), "shadow", &e.variant, &e.client);
+            self.check(e.tag.len() == 1 && e.tag.is_ascii(), || {
+                format!("shadow tag {:?} must be one ASCII byte", e.tag)
+            });
+        }
+
+        self.unique(
+            "substring variant",
+            registry.substring.iter().map(|e| e.variant.as_str()),
+        );
+        let mut earlier_rules: Vec<(&str, &str, usize)> = vec![];
+        for e in &registry.substring {
+            self.client(
+                (&clients, &mut referenced),
+                "substring",
+                &e.variant,
+                &e.client,
+            );
+            self.check(!e.rules.is_empty(), || {
+                format!("substring variant {:?} has no rules", e.variant)
+            });
+
+            for (substr, offset) in &e.rules {
+                self.check(
+                    !substr.is_empty() && substr.is_ascii() && offset + substr.len() <= 20,
+                    || format!("substring rule {substr:?} at {offset} doesn't fit a peer ID"),
+                );
+                // rules are tried in order, so a rule is dead if an earlier one matches
+                // everything it matches
+                for (variant, earlier, earlier_offset) in &earlier_rules {
+                    let shadowed = *earlier_offset >= *offset
+                        && substr
+                            .get(earlier_offset - offset..)
+                            .is_some_and(|rest| rest.starts_with(earlier));
+                    self.check(!shadowed, || {
+                        format!(
+                            "substring rule {substr:?} at {offset} of {:?} is shadowed by \
+                             {earlier:?} at {earlier_offset} of {variant:?}",
+                            e.variant
+                        )
+                    });
+                }
+                earlier_rules.push((&e.variant, substr, *offset));
+            }
+
+            let fixed = e.version == SubstringVersion::Fixed;
+            self.check(fixed == e.fixed.is_some(), || {
+                format!(
+                    "substring variant {:?} must set `fixed` if and only if its version is fixed",
+                    e.variant
+                )
+            });
+            self.check(fixed || (e.numeric.is_none() && e.pre_release.is_none()), || {
+                format!(
+                    "substring variant {:?} sets `numeric` or `pre_release` without a fixed version",
+                    e.variant
+                )
+            });
+            if let Some(numeric) = &e.numeric {
+                self.check((1..=4).contains(&numeric.len()), || {
+                    format!(
+                        "substring variant {:?} must have 1 to 4 numeric components",
+                        e.variant
+                    )
+                });
+            }
+            self.check(e.numeric.is_some() || e.pre_release.is_none(), || {
+                format!(
+                    "substring variant {:?} sets `pre_release` without `numeric`",
+                    e.variant
+                )
+            });
+            if let Some(pre_release) = &e.pre_release {
+                self.check(is_identifier(pre_release), || {
+                    format!(
+                        "substring variant {:?} has a pre-release {pre_release:?} that isn't a valid identifier",
+                        e.variant
+                    )
+                });
+            }
+        }
+
+        for c in &registry.client {
+            self.check(referenced.contains(c.variant.as_str()), || {
+                format!("client {:?} isn't used by any style", c.variant)
+            });
+        }
+
+        if !self.errors.is_empty() {
+            panic!("invalid {REGISTRY}:\n{}", self.errors.join("\n"));
+        }
+    }
+
+    fn metadata(&mut self, registry: &Registry, c: &ClientEntry) {
+        for platform in &c.platforms {
+            self.check(is_identifier(platform), || {
+                format!(
+                    "client {:?} has a platform {platform:?} that isn't a valid identifier",
+                    c.variant
+                )
+            });
+        }
+        self.unique(
+            &format!("platform of {:?}", c.variant),
+            c.platforms.iter().map(String::as_str),
+        );
+        self.check(
+            c.last_release.is_none() || c.maintained != Some(true),
+            || {
+                format!(
+                    "client {:?} is maintained, but has a last release",
+                    c.variant
+                )
+            },
+        );
+        if let (Some(first), Some(last)) = (c.first_release, c.last_release) {
+            self.check(first <= last, || {
+                format!(
+                    "client {:?} was last released before its first release",
+                    c.variant
+                )
+            });
+        }
+        if let Some(engine) = &c.engine {
+            match registry.client.iter().find(|e| &e.variant == engine) {
+                None => self.errors.push(format!(
+                    "client {:?} has an unknown engine {engine:?}",
+                    c.variant
+                )),
+                Some(e) => self.check(e.engine.is_none(), || {
+                    format!(
+                        "the engine of client {:?}, {engine:?}, has an engine of its own",
+                        c.variant
+                    )
+                }),
+            }
+        }
     }
 }
 
-fn main() {
-    let all_tag_maps = vec![codegen_tags_azureus(), codegen_tags_shadow()];
+fn codegen_known_clients(registry: &Registry) -> String {
+    let mut out = String::new();
+    out.push_str(
+        "/// Every client the library can recognise. Some clients use several peer ID encodings over\n\
+         /// their history, they are all mapped onto a single variant here.\n\
+         ///\n\
+         /// Use [`Display`](fmt::Display) to get a human-readable name, and [`KnownClient::id`] to get\n\
+         /// a stable ASCII identifier suitable for storage. [`FromStr`] parses the identifier back.\n\
+         #[non_exhaustive]\n\
+         #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]\n\
+         pub enum KnownClient {\n",
+    );
+    for c in &registry.client {
+        writeln!(out, "    #[doc = {:?}]\n    {},", c.name, c.variant).unwrap();
+    }
+    out.push_str("}\n\nimpl KnownClient {\n    fn codegen_id(self) -> &'static str {\n        match self {\n");
+    for c in &registry.client {
+        writeln!(out, "            Self::{} => {:?},", c.variant, c.id).unwrap();
+    }
+    out.push_str(
+        "        }\n    }\n\n    fn codegen_name(self) -> &'static str {\n        match self {\n",
+    );
+    for c in &registry.client {
+        writeln!(out, "            Self::{} => {:?},", c.variant, c.name).unwrap();
+    }
+    out.push_str(
+        "        }\n    }\n\n    fn codegen_aliases(self) -> &'static [&'static str] {\n        match self {\n",
+    );
+    for c in &registry.client {
+        writeln!(out, "            Self::{} => &{:?},", c.variant, c.aliases).unwrap();
+    }
+    out.push_str("        }\n    }\n}\n\nconst ALL: &[KnownClient] = &[\n");
+    for c in &registry.client {
+        writeln!(out, "    KnownClient::{},", c.variant).unwrap();
+    }
+    out.push_str("];\n");
+    out
+}
+
+fn codegen_client_info(registry: &Registry) -> String {
+    let mut out = String::new();
+    out.push_str(
+        "impl KnownClient {\n    #[allow(clippy::too_many_lines)]\n    fn codegen_info(self) -> ClientInfo {\n        match self {\n",
+    );
+    for c in &registry.client {
+        let platforms = c
+            .platforms
+            .iter()
+            .map(|p| format!("Platform::{p}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            out,
+            "            Self::{} => ClientInfo {{\n\
+             \x20               homepage: {:?},\n\
+             \x20               maintained: {:?},\n\
+             \x20               first_release: {:?},\n\
+             \x20               last_release: {:?},\n\
+             \x20               platforms: &[{platforms}],\n\
+             \x20               license: {:?},\n\
+             \x20               engine: {},\n\
+             \x20           }},",
+            c.variant,
+            c.homepage,
+            c.maintained,
+            c.first_release,
+            c.last_release,
+            c.license,
+            match &c.engine {
+                Some(engine) => format!("Some(KnownClient::{engine})"),
+                None => "None".to_string(),
+            },
+        )
+        .unwrap();
+    }
+    out.push_str("        }\n    }\n}\n");
+    out
+}
+
+// the parts every style-specific enum shares: the enum itself, the mapping onto the
+// canonical clients and, optionally, the list of all variants
+fn codegen_style_enum<'a>(
+    name: &str,
+    derives: &str,
+    with_all: bool,
+    entries: impl Iterator<Item = (&'a str, &'a Option<String>)> + Clone,
+) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "#[non_exhaustive]\n#[derive({derives})]\npub(crate) enum {name} {{"
+    )
+    .unwrap();
+    for (variant, _) in entries.clone() {
+        writeln!(out, "    {variant},").unwrap();
+    }
+    out.push_str("}\n\n");
+    if with_all {
+        writeln!(out, "const ALL: &[{name}] = &[").unwrap();
+        for (variant, _) in entries.clone() {
+            writeln!(out, "    {name}::{variant},").unwrap();
+        }
+        out.push_str("];\n\n");
+    }
+    writeln!(
+        out,
+        "impl {name} {{\n    fn canonical(self) -> KnownClient {{\n        match self {{"
+    )
+    .unwrap();
+    for (variant, client) in entries {
+        writeln!(
+            out,
+            "            Self::{variant} => KnownClient::{},",
+            canonical(variant, client)
+        )
+        .unwrap();
+    }
+    out.push_str("        }\n    }\n}\n\n");
+    out
+}
+
+fn codegen_azureus(registry: &Registry) -> String {
+    let entries = registry
+        .azureus
+        .iter()
+        .map(|e| (e.variant.as_str(), &e.client));
+    let mut out = codegen_style_enum(
+        "Azureus",
+        "Debug, Copy, Clone, Eq, PartialEq",
+        true,
+        entries,
+    );
 
+    out.push_str("impl Azureus {\n    // the version encoding each client uses\n    pub(crate) fn version_style(self) -> StyleKind {\n        match self {\n");
+    for e in &registry.azureus {
+        writeln!(
+            out,
+            "            Self::{} => StyleKind::{},",
+            e.variant, e.style
+        )
+        .unwrap();
+    }
+    out.push_str("        }\n    }\n}\n\n");
+
+    let mut tags = phf_codegen::Map::new();
+    for e in &registry.azureus {
+        for tag in &e.tags {
+            tags.entry(tag.as_bytes(), &format!("Azureus::{}", e.variant));
+        }
+    }
+    writeln!(
+        out,
+        "static TAGS: phf::Map<&'static [u8], Azureus> =\n{};",
+        tags.build()
+    )
+    .unwrap();
+    out
+}
+
+fn codegen_shadow(registry: &Registry) -> String {
+    let entries = registry
+        .shadow
+        .iter()
+        .map(|e| (e.variant.as_str(), &e.client));
+    let mut out = codegen_style_enum("Shadow", "Debug, Copy, Clone, Eq, PartialEq", true, entries);
+
+    let mut tags = phf_codegen::Map::new();
+    for e in &registry.shadow {
+        tags.entry(e.tag.as_bytes()[0], &format!("Shadow::{}", e.variant));
+    }
+    writeln!(
+        out,
+        "static TAGS: phf::Map<u8, Shadow> =\n{};",
+        tags.build()
+    )
+    .unwrap();
+    out
+}
+
+fn codegen_substring(registry: &Registry) -> String {
+    let entries = registry
+        .substring
+        .iter()
+        .map(|e| (e.variant.as_str(), &e.client));
+    let mut out = codegen_style_enum("Substring", "Debug, Copy, Clone", false, entries);
+
+    out.push_str(
+        "impl Substring {\n    fn version(self) -> Option<Version> {\n        match self {\n",
+    );
+    for e in &registry.substring {
+        let version = match (&e.version, &e.fixed) {
+            (SubstringVersion::None, _) => "None".to_string(),
+            (SubstringVersion::Unknown, _) => "Some(Version::Unknown)".to_string(),
+            (SubstringVersion::Fixed, Some(fixed)) => {
+                let numeric = match &e.numeric {
+                    None => "None".to_string(),
+                    Some(components) => format!(
+                        "Some(NumericVersion::new(&{components:?}, {}))",
+                        match &e.pre_release {
+                            None => "None".to_string(),
+                            Some(p) => format!("Some(PreRelease::{p})"),
+                        }
+                    ),
+                };
+                format!("Some(Version::Fixed({fixed:?}, {numeric}))")
+            }
+            (SubstringVersion::Fixed, None) => unreachable!("checked by the validator"),
+        };
+        writeln!(out, "            Self::{} => {version},", e.variant).unwrap();
+    }
+    out.push_str("        }\n    }\n}\n\n");
+
+    let rules = registry
+        .substring
+        .iter()
+        .flat_map(|e| e.rules.iter().map(move |r| (&e.variant, r)))
+        .collect::<Vec<_>>();
+    writeln!(out, "const VARIANTS: [Variant; {}] = [", rules.len()).unwrap();
+    for (variant, (substr, offset)) in rules {
+        // `{:?}` of a `str` writes `\u{..}` escapes, which byte strings don't accept
+        writeln!(
+            out,
+            "    Variant(Substring::{variant}, b\"{}\", {offset}),",
+            substr.as_bytes().escape_ascii()
+        )
+        .unwrap();
+    }
+    out.push_str("];\n");
+    out
+}
+
+fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={REGISTRY}");
 
-    for tag_map in all_tag_maps {
-        let path = Path::new(&env::var("OUT_DIR").unwrap()).join(tag_map.filename);
+    let source = fs::read_to_string(REGISTRY).unwrap();
+    let registry: Registry =
+        toml::from_str(&source).unwrap_or_else(|e| panic!("can't parse {REGISTRY}: {e}"));
+    Validator::default().validate(&registry);
 
-        let mut file = BufWriter::new(File::create(&path).unwrap());
+    let files: HashMap<&str, String> = HashMap::from([
+        ("codegen_known_clients.rs", codegen_known_clients(&registry)),
+        ("codegen_client_info.rs", codegen_client_info(&registry)),
+        ("codegen_azureus.rs", codegen_azureus(&registry)),
+        ("codegen_shadow.rs", codegen_shadow(&registry)),
+        ("codegen_substring.rs", codegen_substring(&registry)),
+    ]);
 
-        writeln!(&mut file, "{}\n{};", tag_map.definition, tag_map.phf).unwrap();
+    for (filename, contents) in files {
+        let path = Path::new(&env::var("OUT_DIR").unwrap()).join(filename);
+        let mut file = BufWriter::new(File::create(&path).unwrap());
+        file.write_all(contents.as_bytes()).unwrap();
     }
 }
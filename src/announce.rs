@@ -0,0 +1,170 @@
+// HTTP trackers receive the peer ID percent-encoded in the announce query string, see
+// BEP 3 (https://www.bittorrent.org/beps/bep_0003.html#trackers)
+
+use crate::errors::AnnounceError;
+use crate::{parse, parse_structured, Parsed, ParsedStructured};
+use tdyne_peer_id::PeerId;
+
+fn hex_digit(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Percent-decodes a `peer_id` value taken from an announce query string, e.g.
+/// `%2DTR4040%2Dxxxxxxxxxxxx`, and checks that it is 20 bytes long. Only `%XX` sequences
+/// are decoded, a `+` is a literal `+`: clients percent-encode the raw peer ID bytes, they
+/// don't submit HTML forms.
+///
+/// Use [`peer_id_from_announce`] to extract the value from the query string as well.
+pub fn decode_peer_id(value: impl AsRef<[u8]>) -> Result<PeerId, AnnounceError> {
+    let mut decoded = [0u8; 20];
+    let mut len = 0;
+
+    let mut bytes = value.as_ref().iter().copied();
+    while let Some(b) = bytes.next() {
+        let b = match b {
+            b'%' => {
+                let hi = bytes.next().and_then(hex_digit);
+                let lo = bytes.next().and_then(hex_digit);
+                match (hi, lo) {
+                    (Some(hi), Some(lo)) => hi << 4 | lo,
+                    _ => return Err(AnnounceError::InvalidPercentEncoding),
+                }
+            }
+            other => other,
+        };
+
+        // I keep counting past 20 to report the actual length
+        if let Some(x) = decoded.get_mut(len) {
+            *x = b;
+        }
+        len += 1;
+    }
+
+    if len != decoded.len() {
+        return Err(AnnounceError::WrongLength(len));
+    }
+    Ok(PeerId::from(decoded))
+}
+
+/// Extracts and decodes the `peer_id` parameter from an announce URL
+/// (`http://tracker.example/announce?info_hash=...&peer_id=...`) or from just its query
+/// string. If the parameter is repeated, the first one is used.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::peer_id_from_announce;
+///
+/// let peer_id = peer_id_from_announce(
+///     "/announce?info_hash=%12%34&peer_id=%2DTR4040%2D%00%01d7xkqq04n%FF&port=51413"
+/// ).unwrap();
+/// assert_eq!(&peer_id.0, b"-TR4040-\x00\x01d7xkqq04n\xff");
+/// ```
+pub fn peer_id_from_announce(announce: impl AsRef<[u8]>) -> Result<PeerId, AnnounceError> {
+    let announce = announce.as_ref();
+
+    // everything up to `?` is the path, everything after `#` is the fragment
+    let query = match announce.iter().position(|&b| b == b'?') {
+        Some(i) => &announce[i + 1..],
+        None => announce,
+    };
+    let query = match query.iter().position(|&b| b == b'#') {
+        Some(i) => &query[..i],
+        None => query,
+    };
+
+    let value = query
+        .split(|&b| b == b'&')
+        .find_map(|param| param.strip_prefix(b"peer_id="))
+        .ok_or(AnnounceError::MissingPeerId)?;
+
+    decode_peer_id(value)
+}
+
+/// Same as [`parse`], but takes an announce URL or query string, see
+/// [`peer_id_from_announce`].
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::parse_announce;
+///
+/// let parsed = parse_announce("info_hash=%12%34&peer_id=%2DTR4040%2Dxxxxxxxxxxxx").unwrap();
+/// assert_eq!(parsed.client, "Transmission");
+/// ```
+pub fn parse_announce(announce: impl AsRef<[u8]>) -> Result<Parsed, AnnounceError> {
+    Ok(parse(peer_id_from_announce(announce)?)?)
+}
+
+/// Same as [`parse_structured`], but takes an announce URL or query string, see
+/// [`peer_id_from_announce`].
+pub fn parse_announce_structured(
+    announce: impl AsRef<[u8]>,
+) -> Result<ParsedStructured, AnnounceError> {
+    Ok(parse_structured(peer_id_from_announce(announce)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ClientParsingError;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case("peer_id=-TR4040-xxxxxxxxxxxx" ; "bare")]
+    #[test_case("peer_id=%2dTR4040%2Dxxxxxxxxxxxx" ; "mixed case")]
+    #[test_case("peer_id=%2D%54%52%34%30%34%30%2D%78%78%78%78%78%78%78%78%78%78%78%78" ; "all encoded")]
+    #[test_case("info_hash=%00%00&peer_id=-TR4040-xxxxxxxxxxxx&port=6881" ; "middle")]
+    #[test_case("http://t.example:80/announce?peer_id=-TR4040-xxxxxxxxxxxx#x" ; "url")]
+    #[test_case("peer_id=-TR4040-xxxxxxxxxxxx&peer_id=-UT355B-xxxxxxxxxxxx" ; "repeated")]
+    #[test_case("xpeer_id=-UT355B-xxxxxxxxxxxx&peer_id=-TR4040-xxxxxxxxxxxx" ; "similar key")]
+    fn test_extract(announce: &str) {
+        assert_eq!(
+            &peer_id_from_announce(announce).unwrap().0,
+            b"-TR4040-xxxxxxxxxxxx"
+        );
+    }
+
+    #[test]
+    fn test_plus() {
+        assert_eq!(
+            &decode_peer_id("-TR4040-xxxxxxxxxx+%2B").unwrap().0,
+            b"-TR4040-xxxxxxxxxx++"
+        );
+        assert_eq!(
+            &decode_peer_id("-TR4040-xxxxxxxxxx+%20").unwrap().0,
+            b"-TR4040-xxxxxxxxxx+ "
+        );
+    }
+
+    #[test_case("info_hash=%00%00", AnnounceError::MissingPeerId)]
+    #[test_case("/announce?peer=-TR4040-xxxxxxxxxxxx", AnnounceError::MissingPeerId)]
+    #[test_case(
+        "peer_id=%2DTR4040%2Dxxxxxxxxxxx%",
+        AnnounceError::InvalidPercentEncoding
+    )]
+    #[test_case(
+        "peer_id=%2DTR4040%2Dxxxxxxxxxxx%G0",
+        AnnounceError::InvalidPercentEncoding
+    )]
+    #[test_case("peer_id=-TR4040-xxxxxxxxxxx", AnnounceError::WrongLength(19))]
+    #[test_case("peer_id=-TR4040-xxxxxxxxxxxx%00", AnnounceError::WrongLength(21))]
+    #[test_case("peer_id=", AnnounceError::WrongLength(0))]
+    fn test_extract_error(announce: &str, error: AnnounceError) {
+        assert_eq!(peer_id_from_announce(announce).unwrap_err(), error);
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = parse_announce_structured("peer_id=%2DZZ1234%2Dxxxxxxxxxxxx").unwrap_err();
+        assert_eq!(
+            parsed,
+            AnnounceError::ClientParsing(ClientParsingError::UnknownAzureusStylePrefix(*b"ZZ"))
+        );
+    }
+}
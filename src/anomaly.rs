@@ -0,0 +1,193 @@
+// signs that a peer ID wasn't generated by the client it claims to be, e.g. cheating
+// clients that masquerade as popular ones
+
+use crate::client::has_spoof_marker;
+use crate::client_info::Family;
+use crate::client_styles::Style;
+use crate::errors::VersionParsingError;
+use crate::known_clients::KnownClient;
+use crate::version::{AzureusVersion, Transmission, Version};
+use crate::{parse_structured, ParsedStructured};
+use std::fmt;
+use tdyne_peer_id::PeerId;
+
+// the alphabet libtorrent's `url_random()` fills the random part with
+const LIBTORRENT_ALPHABET: &[u8] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ-_.!~*()";
+
+// Transmission uses base 36 since 3.0, and the last symbol is a checksum, see
+// `tr_peerIdInit()`. Older versions generate the random part differently
+const TRANSMISSION_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+// the random part follows the `-XX1234-` header
+const AZUREUS_RANDOM_PART: usize = 8;
+
+/// Something suspicious about a peer ID, returned by [`detect_anomalies`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Anomaly {
+    /// The peer ID ends with `UDP0` or `HTTPBT`, the marker BitSpirit and BitComet use
+    /// when they pretend to be other clients.
+    SpoofMarker,
+    /// The random part of the peer ID has a byte the claimed client's generator never
+    /// produces. Includes the offset of the first such byte and the byte itself.
+    UnexpectedRandomByte(
+        usize,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8,
+    ),
+    /// The peer ID claims to be Transmission 3.0 or newer, but the last byte isn't the
+    /// checksum Transmission ends its peer IDs with.
+    BadChecksum,
+    /// The client is recognised, but its version doesn't decode. Real clients always
+    /// encode valid versions.
+    ImpossibleVersion(VersionParsingError),
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SpoofMarker => {
+                write!(f, "Ends with a BitSpirit/BitComet spoof mode marker")
+            }
+            Self::UnexpectedRandomByte(offset, b) => {
+                write!(
+                    f,
+                    "Byte {offset} ({}) can't be produced by the client's generator",
+                    b.escape_ascii()
+                )
+            }
+            Self::BadChecksum => {
+                write!(f, "Transmission checksum doesn't match")
+            }
+            Self::ImpossibleVersion(e) => {
+                write!(f, "Impossible version: {e}")
+            }
+        }
+    }
+}
+
+/// Checks a peer ID for signs that it wasn't generated by the client it claims to be:
+/// spoof mode markers, versions that don't decode, and random parts that don't come from
+/// the claimed client's generator. Random parts are only checked for clients whose
+/// generator is known: Transmission since 3.0 and the libtorrent (Rasterbar) family.
+///
+/// Returns an empty list for unremarkable and unrecognised peer IDs.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::{detect_anomalies, Anomaly};
+///
+/// assert_eq!(detect_anomalies(PeerId::from(b"-TR4040-abcdefghijkf")), vec![]);
+///
+/// // Transmission only uses lowercase letters and digits
+/// assert_eq!(
+///     detect_anomalies(PeerId::from(b"-TR4040-ABCDEFGHIJKF")),
+///     vec![Anomaly::UnexpectedRandomByte(8, b'A')]
+/// );
+/// ```
+pub fn detect_anomalies(peer_id: PeerId) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+
+    if has_spoof_marker(peer_id.0) {
+        anomalies.push(Anomaly::SpoofMarker);
+    }
+
+    let Ok(parsed) = parse_structured(peer_id) else {
+        return anomalies;
+    };
+
+    if let Err(e) = parsed.version {
+        anomalies.push(Anomaly::ImpossibleVersion(e));
+    }
+
+    if parsed.style == Style::Azureus {
+        anomalies.extend(check_random_part(&parsed, peer_id));
+    }
+
+    anomalies
+}
+
+fn check_random_part(parsed: &ParsedStructured, peer_id: PeerId) -> Option<Anomaly> {
+    let transmission = is_current_transmission(parsed);
+    let alphabet = if transmission {
+        TRANSMISSION_ALPHABET
+    } else if parsed.client.family() == Some(Family::Libtorrent) {
+        LIBTORRENT_ALPHABET
+    } else {
+        return None;
+    };
+
+    let random = &peer_id.0[AZUREUS_RANDOM_PART..];
+    if let Some(i) = random.iter().position(|b| !alphabet.contains(b)) {
+        return Some(Anomaly::UnexpectedRandomByte(
+            AZUREUS_RANDOM_PART + i,
+            random[i],
+        ));
+    }
+
+    if transmission && !transmission_checksum_matches(random) {
+        return Some(Anomaly::BadChecksum);
+    }
+
+    None
+}
+
+fn is_current_transmission(parsed: &ParsedStructured) -> bool {
+    parsed.client == KnownClient::Transmission
+        && matches!(
+            parsed.version,
+            Ok(Some(Version::Azureus(AzureusVersion::Transmission(
+                Transmission::Current(..)
+            ))))
+        )
+}
+
+// Transmission picks the last symbol so that all the symbol values add up to a multiple of 36
+fn transmission_checksum_matches(random: &[u8]) -> bool {
+    let total: usize = random
+        .iter()
+        .filter_map(|b| TRANSMISSION_ALPHABET.iter().position(|x| x == b))
+        .sum();
+    total % TRANSMISSION_ALPHABET.len() == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(b"-TR4040-abcdefghijkf", &[])]
+    #[test_case(b"-TR4040-xxxxxxxxxxxx", &[])]
+    #[test_case(b"-TR4040-abcdefghijkg", &[Anomaly::BadChecksum])]
+    #[test_case(b"-TR4040-abcdefghij\xffk", &[Anomaly::UnexpectedRandomByte(18, 0xff)])]
+    #[test_case(b"-qB4630-abc(DEF)~*!_", &[])]
+    #[test_case(b"-qB4630-abc\x00efghijkl", &[Anomaly::UnexpectedRandomByte(11, 0)])]
+    #[test_case(b"-LT2090-a/cdefghijkl", &[Anomaly::UnexpectedRandomByte(9, b'/')])]
+    #[test_case(
+        b"-qBx630-xxxxxxxxxxxx",
+        &[Anomaly::ImpossibleVersion(VersionParsingError::DigitNotBase16(b'x'))]
+    )]
+    #[test_case(b"-TR4040-xxxxxxxxUDP0", &[Anomaly::SpoofMarker])]
+    // the base 36 alphabet and the checksum only apply since 3.0
+    #[test_case(b"-TR2940-ABCDEFGHIJKL", &[])]
+    #[test_case(b"-TR0072-\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b", &[])]
+    #[test_case(b"-TR3000-abcdefghijkg", &[Anomaly::BadChecksum])]
+    // µTorrent fills the random part with arbitrary bytes
+    #[test_case(b"-UT355B-\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b", &[])]
+    #[test_case(b"-ZZ1234-\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b", &[])]
+    fn test_detect_anomalies(peer_id: &[u8; 20], expected: &[Anomaly]) {
+        assert_eq!(detect_anomalies(PeerId::from(peer_id)), expected);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Anomaly::UnexpectedRandomByte(11, 0).to_string(),
+            "Byte 11 (\\x00) can't be produced by the client's generator"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+// turns the textual forms peer IDs show up in (logs, announce URLs, packet dumps) back
+// into the 20 raw bytes
+
+use base64::Engine;
+use std::fmt;
+use tdyne_peer_id_registry::errors::AnnounceError;
+use tdyne_peer_id_registry::{decode_peer_id, peer_id_from_announce};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, clap::ValueEnum)]
+pub enum Encoding {
+    /// Guess the encoding from the shape of the input
+    Auto,
+    /// 20 bytes as is
+    Raw,
+    /// 40 hex digits
+    Hex,
+    /// Percent-encoded, either just the value or a whole announce URL or query string
+    Percent,
+    /// Standard base64, with or without padding
+    Base64,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DecodingError {
+    // couldn't guess the encoding in auto mode
+    UnknownEncoding,
+    InvalidHex,
+    Announce(AnnounceError),
+    InvalidBase64,
+    // decoded fine, but into the wrong number of bytes
+    WrongLength(usize),
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownEncoding => write!(f, "Can't guess the encoding"),
+            Self::InvalidHex => write!(f, "Invalid hex"),
+            Self::Announce(e) => e.fmt(f),
+            Self::InvalidBase64 => write!(f, "Invalid base64"),
+            Self::WrongLength(len) => write!(f, "Decoded into {len} bytes instead of 20"),
+        }
+    }
+}
+
+pub fn decode(input: &[u8], encoding: Encoding) -> Result<[u8; 20], DecodingError> {
+    let bytes = match encoding {
+        Encoding::Auto => return decode(input, guess(input)?),
+        Encoding::Raw => input.to_vec(),
+        Encoding::Hex => decode_hex(input)?,
+        Encoding::Percent => return decode_percent(input).map_err(DecodingError::Announce),
+        Encoding::Base64 => base64::engine::general_purpose::STANDARD_NO_PAD
+            .decode(input.strip_suffix(b"=").unwrap_or(input))
+            .map_err(|_| DecodingError::InvalidBase64)?,
+    };
+
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| DecodingError::WrongLength(len))
+}
+
+fn guess(input: &[u8]) -> Result<Encoding, DecodingError> {
+    // I check the raw form first: a 20-byte peer ID can contain anything, including `%`,
+    // while all the other forms are longer than 20 bytes
+    Ok(match input.len() {
+        20 => Encoding::Raw,
+        40 if input.iter().all(|b| b.is_ascii_hexdigit()) => Encoding::Hex,
+        _ if input.contains(&b'%') || is_announce(input) => Encoding::Percent,
+        // 20 bytes take 27 base64 digits, plus one byte of padding
+        27 | 28 => Encoding::Base64,
+        _ => return Err(DecodingError::UnknownEncoding),
+    })
+}
+
+fn decode_hex(input: &[u8]) -> Result<Vec<u8>, DecodingError> {
+    if input.len() % 2 != 0 {
+        return Err(DecodingError::InvalidHex);
+    }
+
+    input
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+                .ok_or(DecodingError::InvalidHex)
+        })
+        .collect()
+}
+
+fn is_announce(input: &[u8]) -> bool {
+    input.windows(8).any(|w| w == b"peer_id=")
+}
+
+fn decode_percent(input: &[u8]) -> Result<[u8; 20], AnnounceError> {
+    let peer_id = if is_announce(input) {
+        peer_id_from_announce(input)?
+    } else {
+        decode_peer_id(input)?
+    };
+    Ok(peer_id.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    const PEER_ID: &[u8; 20] = b"-TR4040-\x00\x01d7xkqq04n\xff";
+
+    #[test_case("2d5452343034302d000164377878b6b71713034fff", Encoding::Auto ; "too long hex")]
+    #[test_case("2d5452343034302d000164377878b6b71713034gff", Encoding::Hex ; "bad hex")]
+    #[test_case("%2DTR4040%2D%00%01d7xkqq04n%F", Encoding::Auto ; "truncated percent")]
+    #[test_case("-TR4040-", Encoding::Auto ; "too short")]
+    #[test_case("LVRSNDA0MC0AAWQ3eGtxcTA0bv8!", Encoding::Auto ; "bad base64")]
+    fn test_decode_error(input: &str, encoding: Encoding) {
+        assert!(decode(input.as_bytes(), encoding).is_err());
+    }
+
+    #[test_case("-TR4040-xxxxxxxxxxxx", b"-TR4040-xxxxxxxxxxxx" ; "raw")]
+    #[test_case("2d5452343034302d787878787878787878787878", b"-TR4040-xxxxxxxxxxxx" ; "hex")]
+    #[test_case("%2DTR4040%2D%00%01d7xkqq04n%FF", PEER_ID ; "percent")]
+    #[test_case("/announce?port=1&peer_id=%2DTR4040%2D%00%01d7xkqq04n%FF", PEER_ID ; "announce")]
+    #[test_case("peer_id=-TR4040-xxxxxxxxxxxx", b"-TR4040-xxxxxxxxxxxx" ; "unencoded announce")]
+    #[test_case("LVRSNDA0MC0AAWQ3eGtxcTA0bv8=", PEER_ID ; "base64")]
+    #[test_case("LVRSNDA0MC0AAWQ3eGtxcTA0bv8", PEER_ID ; "unpadded base64")]
+    fn test_auto(input: &str, expected: &[u8; 20]) {
+        assert_eq!(decode(input.as_bytes(), Encoding::Auto), Ok(*expected));
+    }
+}
@@ -0,0 +1,97 @@
+//! Inspects BitTorrent peer IDs: prints the client, the version and the encoding style.
+//!
+//! Peer IDs are taken from the arguments or, if there are none, from stdin, one per line.
+
+mod input;
+mod output;
+
+use crate::input::{decode, Encoding};
+use crate::output::{write_header, write_record, Error, Format, Record};
+use clap::Parser;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+use tdyne_peer_id::PeerId;
+use tdyne_peer_id_registry::parse_structured;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// How the peer IDs are encoded
+    #[arg(short, long, value_enum, default_value_t = Encoding::Auto)]
+    encoding: Encoding,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Peer IDs to inspect. Read from stdin, one per line, if none are given.
+    /// Azureus-style peer IDs start with a dash, put them after `--` if they clash
+    /// with the options
+    #[arg(allow_hyphen_values = true)]
+    peer_ids: Vec<String>,
+}
+
+fn inspect(input: &[u8], encoding: Encoding) -> Record<'_> {
+    let peer_id = match decode(input, encoding) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return Record {
+                input,
+                peer_id: None,
+                result: Err(Error::Decoding(e)),
+            }
+        }
+    };
+
+    Record {
+        input,
+        peer_id: Some(peer_id),
+        result: parse_structured(PeerId::from(peer_id)).map_err(Error::Client),
+    }
+}
+
+fn run(args: Args) -> io::Result<()> {
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    write_header(&mut out, args.format)?;
+
+    if !args.peer_ids.is_empty() {
+        for peer_id in &args.peer_ids {
+            write_record(
+                &mut out,
+                args.format,
+                &inspect(peer_id.as_bytes(), args.encoding),
+            )?;
+        }
+        return out.flush();
+    }
+
+    // raw peer IDs aren't necessarily UTF-8, so I read bytes rather than strings
+    let mut stdin = io::stdin().lock();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if stdin.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let input = line.strip_suffix(b"\n").unwrap_or(&line);
+        let input = input.strip_suffix(b"\r").unwrap_or(input);
+        if input.is_empty() {
+            continue;
+        }
+        write_record(&mut out, args.format, &inspect(input, args.encoding))?;
+    }
+
+    out.flush()
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        // a closed pipe (e.g. `| head`) is not an error worth reporting
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("tdyne-peer-id: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::input::DecodingError;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use tdyne_peer_id_registry::errors::ClientParsingError;
+use tdyne_peer_id_registry::ParsedStructured;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    /// One human-readable line per peer ID
+    Text,
+    /// One JSON object per line
+    Json,
+    /// CSV with a header row
+    Csv,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Decoding(DecodingError),
+    Client(ClientParsingError),
+}
+
+pub struct Record<'a> {
+    pub input: &'a [u8],
+    pub peer_id: Option<[u8; 20]>,
+    pub result: Result<ParsedStructured, Error>,
+}
+
+// the flat representation shared by all formats, empty strings stand for missing values
+struct Fields {
+    input: String,
+    peer_id: String,
+    client: String,
+    client_id: String,
+    version: String,
+    style: String,
+    version_error: String,
+    error: String,
+}
+
+const CSV_HEADER: &str = "input,peer_id,client,client_id,version,style,version_error,error";
+
+impl Record<'_> {
+    fn fields(&self) -> Fields {
+        let mut fields = Fields {
+            input: String::from_utf8_lossy(self.input).into_owned(),
+            peer_id: self.peer_id.map(hex).unwrap_or_default(),
+            client: String::new(),
+            client_id: String::new(),
+            version: String::new(),
+            style: String::new(),
+            version_error: String::new(),
+            error: String::new(),
+        };
+
+        match &self.result {
+            Ok(parsed) => {
+                fields.client = parsed.client.to_string();
+                fields.client_id = parsed.client.id().to_string();
+                fields.style = parsed.style.to_string();
+                match &parsed.version {
+                    Ok(Some(v)) => fields.version = v.to_string(),
+                    Ok(None) => (),
+                    Err(e) => fields.version_error = e.to_string(),
+                }
+            }
+            Err(Error::Decoding(e)) => fields.error = e.to_string(),
+            Err(Error::Client(e)) => fields.error = e.to_string(),
+        }
+
+        fields
+    }
+}
+
+fn hex(bytes: [u8; 20]) -> String {
+    bytes.iter().fold(String::with_capacity(40), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn write_header(out: &mut impl Write, format: Format) -> io::Result<()> {
+    match format {
+        Format::Csv => writeln!(out, "{CSV_HEADER}"),
+        Format::Text | Format::Json => Ok(()),
+    }
+}
+
+pub fn write_record(out: &mut impl Write, format: Format, record: &Record) -> io::Result<()> {
+    let f = record.fields();
+
+    match format {
+        Format::Text => {
+            if !f.error.is_empty() {
+                return writeln!(out, "{}: error: {}", f.input, f.error);
+            }
+            write!(out, "{}: {}", f.input, f.client)?;
+            if !f.version.is_empty() {
+                write!(out, " {}", f.version)?;
+            }
+            write!(out, " [{}]", f.style)?;
+            if !f.version_error.is_empty() {
+                write!(out, " version error: {}", f.version_error)?;
+            }
+            writeln!(out)
+        }
+        Format::Json => {
+            let optional = |s: String| (!s.is_empty()).then_some(s);
+            let value = serde_json::json!({
+                "input": f.input,
+                "peer_id": optional(f.peer_id),
+                "client": optional(f.client),
+                "client_id": optional(f.client_id),
+                "version": optional(f.version),
+                "style": optional(f.style),
+                "version_error": optional(f.version_error),
+                "error": optional(f.error),
+            });
+            writeln!(out, "{value}")
+        }
+        Format::Csv => {
+            let row = [
+                f.input,
+                f.peer_id,
+                f.client,
+                f.client_id,
+                f.version,
+                f.style,
+                f.version_error,
+                f.error,
+            ];
+            let row: Vec<_> = row.iter().map(|s| csv_field(s)).collect();
+            writeln!(out, "{}", row.join(","))
+        }
+    }
+}
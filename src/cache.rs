@@ -0,0 +1,174 @@
+// most of a tracker's announces come from a handful of client versions, so the parsed
+// headers repeat a lot while the random tails never do
+
+use crate::client::cache_key;
+use crate::errors::ClientParsingError;
+use crate::{parse_structured, Parsed, ParsedStructured};
+use std::collections::HashMap;
+use tdyne_peer_id::PeerId;
+
+/// Counters of a [`CachedParser`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheStats {
+    /// Peer IDs answered from the cache.
+    pub hits: u64,
+    /// Peer IDs that were parsed and added to the cache.
+    pub misses: u64,
+    /// Peer IDs that can't be cached and were parsed directly.
+    pub uncached: u64,
+}
+
+/// A parser that memoizes results by the client-identifying header of the peer ID,
+/// e.g. `-TR4040-` for Transmission, so that the random tail doesn't defeat caching.
+///
+/// Only Azureus- and Shad0w-style peer IDs, which are the vast majority in practice, are
+/// cached. The rest are parsed directly and counted in [`CacheStats::uncached`].
+///
+/// The cache holds at most `capacity` headers and is cleared when full, which bounds
+/// the memory used by peers that send random headers.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::{CachedParser, KnownClient};
+///
+/// let mut parser = CachedParser::new(1024);
+/// for peer_id in [b"-TR4040-aaaaaaaaaaaa", b"-TR4040-bbbbbbbbbbbb"] {
+///     let parsed = parser.parse_structured(PeerId::from(peer_id)).unwrap();
+///     assert_eq!(parsed.client, KnownClient::Transmission);
+/// }
+/// assert_eq!(parser.stats().hits, 1);
+/// assert_eq!(parser.stats().misses, 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedParser {
+    cache: HashMap<[u8; 9], Result<ParsedStructured, ClientParsingError>>,
+    capacity: usize,
+    stats: CacheStats,
+}
+
+impl CachedParser {
+    /// Creates a parser that caches up to `capacity` distinct headers.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            cache: HashMap::new(),
+            capacity,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Same as [`parse_structured`](crate::parse_structured), but cached.
+    pub fn parse_structured(
+        &mut self,
+        peer_id: PeerId,
+    ) -> Result<ParsedStructured, ClientParsingError> {
+        let Some(key) = cache_key(peer_id.0) else {
+            self.stats.uncached += 1;
+            return parse_structured(peer_id);
+        };
+
+        if let Some(result) = self.cache.get(&key) {
+            self.stats.hits += 1;
+            return result.clone();
+        }
+
+        self.stats.misses += 1;
+        let result = parse_structured(peer_id);
+        if self.capacity > 0 {
+            if self.cache.len() >= self.capacity {
+                self.cache.clear();
+            }
+            self.cache.insert(key, result.clone());
+        }
+        result
+    }
+
+    /// Same as [`parse`](crate::parse), but cached.
+    pub fn parse(&mut self, peer_id: PeerId) -> Result<Parsed, ClientParsingError> {
+        self.parse_structured(peer_id).map(Parsed::from)
+    }
+
+    /// Returns the hit and miss counters.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Number of headers in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Empties the cache and resets the counters.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.stats = CacheStats::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+    use test_case::test_case;
+
+    #[test_case(b"-TR4040-aaaaaaaaaaaa", b"-TR4040-bbbbbbbbbbbb", true)]
+    #[test_case(b"-TR4040-aaaaaaaaaaaa", b"-TR4041-aaaaaaaaaaaa", false)]
+    #[test_case(b"-BI3000-xxxxxxxxxxxx", b"-BI3000-Axxxxxxxxxxx", false)]
+    #[test_case(b"-BI3000-xxxxxxxxxxxx", b"-BI3000-yxxxxxxxxxxx", true)]
+    #[test_case(b"T03I--008gY6iB6Aq27C", b"T03I--xxxxxxxxxxxxxx", true)]
+    #[test_case(b"-TR4040-aaaaaaaaUDP0", b"-TR4040-bbbbbbbbUDP0", false)]
+    #[test_case(b"M4-4-0--aaaaaaaaaaaa", b"M4-4-0--bbbbbbbbbbbb", false)]
+    fn test_hit(first: &[u8; 20], second: &[u8; 20], hit: bool) {
+        let mut parser = CachedParser::new(16);
+        parser.parse_structured(PeerId::from(first)).ok();
+        parser.parse_structured(PeerId::from(second)).ok();
+        assert_eq!(parser.stats().hits, u64::from(hit));
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut parser = CachedParser::new(2);
+        for peer_id in [
+            b"-TR4040-xxxxxxxxxxxx",
+            b"-TR4041-xxxxxxxxxxxx",
+            b"-TR4042-xxxxxxxxxxxx",
+        ] {
+            parser.parse_structured(PeerId::from(peer_id)).unwrap();
+        }
+        assert_eq!(parser.len(), 1);
+        assert_eq!(parser.stats().misses, 3);
+    }
+
+    proptest! {
+        // the cache key must capture everything the parser looks at
+        #[test]
+        fn test_same_result(
+            header in prop::sample::select(vec![
+                &b"-TR4040-"[..], b"-BI3000-", b"-UT355B-", b"-ZZ1234-", b"-FG", b"T03I--", b"O0----"
+            ]),
+            tail in prop::collection::vec(any::<u8>(), 20),
+            other_tail in prop::collection::vec(prop::sample::select(&b"Ax-UDP0HTBS"[..]), 20),
+        ) {
+            let mut a = [0; 20];
+            let mut b = [0; 20];
+            for (bytes, tail) in [(&mut a, &tail), (&mut b, &other_tail)] {
+                bytes.copy_from_slice(tail);
+                bytes[..header.len()].copy_from_slice(header);
+            }
+
+            let mut parser = CachedParser::new(16);
+            let cached = (parser.parse_structured(PeerId::from(a)), parser.parse_structured(PeerId::from(b)));
+            prop_assert_eq!(cached.0, parse_structured(PeerId::from(a)));
+            prop_assert_eq!(cached.1, parse_structured(PeerId::from(b)));
+        }
+    }
+}
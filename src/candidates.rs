@@ -0,0 +1,244 @@
+// every interpretation of a peer ID, not just the one `parse_structured()` settles on
+
+use crate::client::{check_bitcomet, check_bitspirit, has_spoof_marker, Client};
+use crate::client_styles::azureus::Azureus;
+use crate::client_styles::mainline::Mainline;
+use crate::client_styles::shadow::Shadow;
+use crate::client_styles::substring::Substring;
+use crate::client_styles::types::ClientStyle;
+use crate::client_styles::Style;
+use crate::errors::VersionParsingError;
+use crate::known_clients::KnownClient;
+use crate::version::Version;
+use tdyne_peer_id::PeerId;
+
+/// How reliable a [`Candidate`] is. Variants are ordered from the least to the most reliable,
+/// so candidates can be compared and filtered by confidence.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Confidence {
+    /// Nothing was recognised, the client is a guess. The only such client is
+    /// [`KnownClient::PossibleBitSpirit`].
+    Fallback,
+    /// A known substring was found somewhere in the peer ID.
+    Substring,
+    /// The peer ID only loosely follows a known encoding, e.g. an Azureus-style tag without
+    /// the closing dash, or BitSpirit's `BS` in the middle of an otherwise arbitrary peer ID.
+    Heuristic,
+    /// The peer ID follows a structured encoding and carries a registered tag.
+    ExactTag,
+}
+
+/// One interpretation of a peer ID, returned by [`parse_candidates`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Candidate {
+    /// The client. See [`ParsedStructured::client`](crate::ParsedStructured::client).
+    pub client: KnownClient,
+    /// Encoding convention this interpretation assumes.
+    pub style: Style,
+    /// Version under this interpretation. See
+    /// [`ParsedStructured::version`](crate::ParsedStructured::version).
+    pub version: Result<Option<Version>, VersionParsingError>,
+    /// How much this interpretation can be trusted.
+    pub confidence: Confidence,
+}
+
+/// Returns every client the peer ID can be interpreted as, together with the confidence of
+/// each interpretation.
+///
+/// [`parse_structured`](crate::parse_structured) stops at the first match, checking the
+/// styles in a fixed order. `parse_candidates` checks all of them, and returns the candidates
+/// in the same order, so if `parse_structured` recognises the peer ID, the first candidate
+/// is what it returns. Interpretations with the same client, style and confidence, e.g. two
+/// substrings of the same client, are only listed once, with the version of the first one.
+/// The list is empty if nothing is recognised, use `parse_structured` to find out why.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::{parse_candidates, Confidence, KnownClient};
+///
+/// // a Transmission peer ID with BitSpirit's spoof mode marker at the end
+/// let candidates = parse_candidates(PeerId::from(b"-TR4040-xxxxxxxxUDP0"));
+///
+/// assert_eq!(candidates[0].client, KnownClient::PossibleBitSpirit);
+/// assert_eq!(candidates[0].confidence, Confidence::Fallback);
+/// assert_eq!(candidates[1].client, KnownClient::Transmission);
+/// assert_eq!(candidates[1].confidence, Confidence::ExactTag);
+/// ```
+pub fn parse_candidates(peer_id: PeerId) -> Vec<Candidate> {
+    let mut clients = Vec::new();
+    let spoof_marker = has_spoof_marker(peer_id.0);
+
+    // the order mirrors `Client::try_from()`
+    if spoof_marker {
+        clients.extend(bitspirit_bitcomet(peer_id));
+        if clients.is_empty() {
+            clients.push((Client::PossibleBitSpirit, Confidence::Fallback));
+        }
+    }
+
+    if Azureus::does_match(peer_id.0) {
+        if let Ok(client) = Azureus::parse(peer_id.0) {
+            // clients whose quirks `Azureus::does_match()` tolerates don't close the header
+            let confidence = if peer_id.0[7] == b'-' {
+                Confidence::ExactTag
+            } else {
+                Confidence::Heuristic
+            };
+            clients.push((Client::AzureusStyle(client), confidence));
+        }
+    }
+    if Shadow::does_match(peer_id.0) {
+        if let Ok(client) = Shadow::parse(peer_id.0) {
+            clients.push((Client::ShadowStyle(client), Confidence::ExactTag));
+        }
+    }
+    if Mainline::does_match(peer_id.0) {
+        if let Ok(client) = Mainline::parse(peer_id.0) {
+            clients.push((Client::MainlineStyle(client), Confidence::ExactTag));
+        }
+    }
+
+    if !spoof_marker {
+        clients.extend(bitspirit_bitcomet(peer_id));
+    }
+
+    clients.extend(
+        Substring::matches(peer_id.0).map(|(c, ..)| (Client::Substring(c), Confidence::Substring)),
+    );
+
+    let mut candidates: Vec<Candidate> = Vec::with_capacity(clients.len());
+    for (client, confidence) in clients {
+        let (canonical, style) = (client.to_canonical(), client.style());
+        let duplicate = candidates
+            .iter()
+            .any(|c| c.client == canonical && c.style == style && c.confidence == confidence);
+        if !duplicate {
+            candidates.push(Candidate {
+                client: canonical,
+                style,
+                version: client.parse_version(peer_id.0),
+                confidence,
+            });
+        }
+    }
+    candidates
+}
+
+fn bitspirit_bitcomet(peer_id: PeerId) -> impl Iterator<Item = (Client, Confidence)> {
+    let bitspirit = check_bitspirit(peer_id.0).map(|c| (c, Confidence::Heuristic));
+    let bitcomet = check_bitcomet(peer_id.0).map(|c| (c, Confidence::ExactTag));
+    bitspirit.into_iter().chain(bitcomet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_structured;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+    use test_case::test_case;
+
+    fn summary(peer_id: &[u8; 20]) -> Vec<(KnownClient, Style, Confidence)> {
+        parse_candidates(PeerId::from(peer_id))
+            .into_iter()
+            .map(|c| (c.client, c.style, c.confidence))
+            .collect()
+    }
+
+    #[test_case(
+        b"-TR4040-xxxxxxxxxxxx",
+        &[(KnownClient::Transmission, Style::Azureus, Confidence::ExactTag)]
+    )]
+    #[test_case(
+        b"-TR4040-xxxxxxxxUDP0",
+        &[
+            (KnownClient::PossibleBitSpirit, Style::SpoofMarker, Confidence::Fallback),
+            (KnownClient::Transmission, Style::Azureus, Confidence::ExactTag),
+        ]
+    )]
+    #[test_case(
+        b"-FG1234xxxxxxxxxxxxx",
+        &[(KnownClient::FlashGet, Style::Azureus, Confidence::Heuristic)]
+    )]
+    #[test_case(
+        b"T03I--008gY6iB6Aq27C",
+        &[(KnownClient::BitTornado, Style::Shadow, Confidence::ExactTag)]
+    )]
+    #[test_case(
+        b"M4-4-0--xxxxxxxxxxxx",
+        &[(KnownClient::Mainline, Style::Mainline, Confidence::ExactTag)]
+    )]
+    #[test_case(
+        b"exbc\x00\x4aLORDxxxxxxxxxx",
+        &[(KnownClient::BitLord, Style::BitComet, Confidence::ExactTag)]
+    )]
+    #[test_case(
+        b"\x00\x03BSxxxxxxxxxxxxxxxx",
+        &[(KnownClient::BitSpirit, Style::BitSpirit, Confidence::Heuristic)]
+    )]
+    #[test_case(
+        b"\x00\x03BSxxxxxxxxxxxxUDP0",
+        &[(KnownClient::BitSpirit, Style::BitSpirit, Confidence::Heuristic)]
+    )]
+    // both the 1.7.0 RC substring and the generic `-UT` one match, but it's the same client
+    #[test_case(
+        b"-UT170-xxxxxxxxxxxxx",
+        &[(KnownClient::UTorrent, Style::Substring, Confidence::Substring)]
+    )]
+    #[test_case(b"xxxxxxxxxxxxxxxxxxxx", &[])]
+    fn test_candidates(peer_id: &[u8; 20], expected: &[(KnownClient, Style, Confidence)]) {
+        assert_eq!(summary(peer_id), expected);
+    }
+
+    #[test]
+    fn test_version() {
+        let candidates = parse_candidates(PeerId::from(b"-TR4040-xxxxxxxxUDP0"));
+        assert_eq!(candidates[0].version, Ok(None));
+        assert_eq!(
+            candidates[1].version,
+            parse_structured(PeerId::from(b"-TR4040-xxxxxxxxxxxx"))
+                .unwrap()
+                .version
+        );
+
+        // the merged substring candidate keeps the version of the more specific rule
+        let peer_id = PeerId::from(b"-UT170-xxxxxxxxxxxxx");
+        assert_eq!(
+            parse_candidates(peer_id)[0].version,
+            parse_structured(peer_id).unwrap().version
+        );
+        assert_ne!(
+            parse_candidates(peer_id)[0].version,
+            Ok(Some(Version::Unknown))
+        );
+    }
+
+    // a small alphabet, so that random peer IDs hit the structured styles often enough
+    fn peer_id() -> impl Strategy<Value = [u8; 20]> {
+        prop::array::uniform20(prop::sample::select(b"-0123456789ABDLPSTUMQRexbc".to_vec()))
+    }
+
+    proptest! {
+        #[test]
+        fn test_first_is_parse_structured(bytes in peer_id()) {
+            let peer_id = PeerId::from(&bytes);
+            if let Ok(parsed) = parse_structured(peer_id) {
+                let first = parse_candidates(peer_id).into_iter().next().unwrap();
+                prop_assert_eq!(first.client, parsed.client);
+                prop_assert_eq!(first.style, parsed.style);
+                prop_assert_eq!(first.version, parsed.version);
+            }
+        }
+
+        #[test]
+        fn test_no_panics(bytes in prop::array::uniform20(any::<u8>())) {
+            parse_candidates(PeerId::from(&bytes));
+        }
+    }
+}
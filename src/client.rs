@@ -3,11 +3,62 @@ use crate::client_styles::mainline::Mainline;
 use crate::client_styles::shadow::Shadow;
 use crate::client_styles::substring::Substring;
 use crate::client_styles::types::ClientStyle;
-use crate::errors::{ClientParsingError, VersionParsingError};
+use crate::client_styles::Style;
+use crate::errors::{ClientParsingError, EncodingError, VersionParsingError};
 use crate::known_clients::KnownClient;
 use crate::version::{BitCometMod, Version};
 use crate::version_utils::base10;
-use tdyne_peer_id::PeerId;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::ops::Range;
+
+/// One check made by the parser, in [`Explanation::steps`](crate::Explanation::steps).
+/// Each variant holds whether the check matched.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Step {
+    /// The peer ID ends with the BitSpirit/BitComet spoof mode marker, `UDP0` or `HTTPBT`.
+    SpoofMarker(bool),
+    /// The peer ID has BitSpirit's `BS` at bytes 2 and 3.
+    BitSpirit(bool),
+    /// The peer ID starts with a BitComet/BitLord header, `exbc`, `FUTB` or `xUTB`.
+    BitComet(bool),
+    /// The peer ID has an Azureus-style header, `-XX1234-`.
+    Azureus(bool),
+    /// The peer ID has a Shad0w-style header with a known tag, `X1234-`.
+    Shadow(bool),
+    /// The peer ID has a Mainline-style header, `M1-2-3--`.
+    Mainline(bool),
+    /// The substring table was searched. Holds the first substring found and its offset.
+    Substring(Option<(&'static [u8], usize)>),
+}
+
+fn yes_no(matched: bool) -> &'static str {
+    if matched {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SpoofMarker(m) => write!(f, "spoof mode marker `UDP0`/`HTTPBT`: {}", yes_no(*m)),
+            Self::BitSpirit(m) => write!(f, "BitSpirit `BS` at 2..4: {}", yes_no(*m)),
+            Self::BitComet(m) => write!(f, "BitComet header `exbc`/`FUTB`/`xUTB`: {}", yes_no(*m)),
+            Self::Azureus(m) => write!(f, "Azureus style `-XX1234-`: {}", yes_no(*m)),
+            Self::Shadow(m) => write!(f, "Shad0w style `X1234-`: {}", yes_no(*m)),
+            Self::Mainline(m) => write!(f, "Mainline style `M1-2-3--`: {}", yes_no(*m)),
+            Self::Substring(Some((substring, offset))) => write!(
+                f,
+                "substring table: `{}` at {offset}",
+                substring.escape_ascii()
+            ),
+            Self::Substring(None) => write!(f, "substring table: no match"),
+        }
+    }
+}
 
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone)]
@@ -23,7 +74,7 @@ pub(crate) enum Client {
 }
 
 impl Client {
-    pub fn parse_version(self, peer_id: PeerId) -> Result<Option<Version>, VersionParsingError> {
+    pub fn parse_version(self, peer_id: [u8; 20]) -> Result<Option<Version>, VersionParsingError> {
         match self {
             Self::AzureusStyle(x) => x.parse_version(peer_id),
             Self::ShadowStyle(x) => x.parse_version(peer_id),
@@ -36,6 +87,93 @@ impl Client {
         }
     }
 
+    // the bytes `parse_version()` decodes, for `parse_explained()`
+    #[cfg(feature = "std")]
+    pub fn version_bytes(self, peer_id: [u8; 20]) -> Option<Range<usize>> {
+        match self {
+            Self::AzureusStyle(x) => x.version_style().version_bytes(),
+            Self::ShadowStyle(_) => (peer_id[1] != b'-').then_some(1..5),
+            Self::MainlineStyle(_) => Some(Mainline::version_bytes(peer_id)),
+            Self::Substring(_) | Self::PossibleBitSpirit => None,
+            Self::BitSpirit => Some(1..2),
+            // see `parse_bitcomet_bitlord_version()`
+            Self::BitLord if peer_id[4] != b'0' => Some(4..6),
+            Self::BitComet | Self::BitLord => Some(4..7),
+        }
+    }
+
+    // all clients the encoder can produce peer IDs for, see `encode()` below
+    fn all_encodable() -> impl Iterator<Item = Self> {
+        Azureus::all()
+            .map(Self::AzureusStyle)
+            .chain(Shadow::all().map(Self::ShadowStyle))
+            .chain(Mainline::all().map(Self::MainlineStyle))
+    }
+
+    pub fn encode(
+        client: KnownClient,
+        version: Option<&Version>,
+        random: [u8; 20],
+    ) -> Result<[u8; 20], EncodingError> {
+        let mut error = EncodingError::UnsupportedClient;
+
+        // some clients have more than one encoding (e.g. Ares used both three and four digits),
+        // so I try each one and only give up on a version style mismatch if none of them fit
+        for candidate in Self::all_encodable().filter(|c| c.to_canonical() == client) {
+            let mut bytes = random;
+            let result = match candidate {
+                Self::AzureusStyle(x) => x.encode(version, &mut bytes),
+                Self::ShadowStyle(x) => x.encode(version, &mut bytes),
+                Self::MainlineStyle(x) => x.encode(version, &mut bytes),
+                _ => unreachable!("only structured styles are encodable"),
+            };
+
+            match result {
+                Ok(()) => return Self::check_round_trip(client, version, bytes),
+                Err(EncodingError::MismatchedVersionStyle) => {
+                    error = EncodingError::MismatchedVersionStyle;
+                }
+                Err(EncodingError::UnsupportedClient) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(error)
+    }
+
+    // the encoder guarantees that whatever it produces parses back into the same client and
+    // version, this is the last line of defence in case the per-style encoders miss a quirk
+    fn check_round_trip(
+        client: KnownClient,
+        version: Option<&Version>,
+        peer_id: [u8; 20],
+    ) -> Result<[u8; 20], EncodingError> {
+        // random bytes can trigger the heuristics in `try_from()`, in which case
+        // the peer ID won't parse back to the requested client
+        let parsed = match Self::try_from(peer_id) {
+            Ok(parsed) if parsed.to_canonical() == client => parsed,
+            _ => return Err(EncodingError::ConflictingRandomBytes),
+        };
+
+        if parsed.parse_version(peer_id).as_ref().map(Option::as_ref) != Ok(version) {
+            return Err(EncodingError::UnrepresentableVersion);
+        }
+
+        Ok(peer_id)
+    }
+
+    pub fn style(self) -> Style {
+        match self {
+            Self::AzureusStyle(_) => Style::Azureus,
+            Self::ShadowStyle(_) => Style::Shadow,
+            Self::MainlineStyle(_) => Style::Mainline,
+            Self::Substring(_) => Style::Substring,
+            Self::PossibleBitSpirit => Style::SpoofMarker,
+            Self::BitSpirit => Style::BitSpirit,
+            Self::BitComet | Self::BitLord => Style::BitComet,
+        }
+    }
+
     pub fn to_canonical(self) -> KnownClient {
         match self {
             Self::AzureusStyle(x) => x.to_canonical(),
@@ -50,24 +188,24 @@ impl Client {
     }
 }
 
-fn check_bitspirit(peer_id: PeerId) -> Option<Client> {
-    if &peer_id.0[2..4] == b"BS" {
+pub(crate) fn check_bitspirit(peer_id: [u8; 20]) -> Option<Client> {
+    if &peer_id[2..4] == b"BS" {
         Some(Client::BitSpirit)
     } else {
         None
     }
 }
 
-fn parse_bitspirit_version(peer_id: PeerId) -> Result<Option<Version>, VersionParsingError> {
-    let v1 = base10(peer_id.0[1])?.max(1);
+fn parse_bitspirit_version(peer_id: [u8; 20]) -> Result<Option<Version>, VersionParsingError> {
+    let v1 = base10(peer_id[1])?.max(1);
     Ok(Some(Version::BitSpirit(v1)))
 }
 
 fn parse_bitcomet_bitlord_version(
     client: Client,
-    peer_id: PeerId,
+    peer_id: [u8; 20],
 ) -> Result<Option<Version>, VersionParsingError> {
-    let m = match peer_id.0 {
+    let m = match peer_id {
         [b'e', b'x', b'b', b'c', ..] => None,
         [b'F', b'U', b'T', b'B', ..] => Some(BitCometMod::Solidox),
         [b'x', b'U', b'T', b'B', ..] => Some(BitCometMod::Mod2),
@@ -77,21 +215,19 @@ fn parse_bitcomet_bitlord_version(
             ))
         }
     };
-    let v1 = base10(peer_id.0[4])?;
+    let v1 = base10(peer_id[4])?;
     let v2 = if matches!(client, Client::BitLord) && v1 > 0 {
-        base10(peer_id.0[5])?
+        base10(peer_id[5])?
     } else {
-        base10(peer_id.0[5])? * 10 + base10(peer_id.0[6])?
+        base10(peer_id[5])? * 10 + base10(peer_id[6])?
     };
     Ok(Some(Version::BitCometBitLord(v1, v2, m)))
 }
 
-fn check_bitcomet(peer_id: PeerId) -> Option<Client> {
-    if peer_id.0.starts_with(b"exbc")
-        || peer_id.0.starts_with(b"FUTB")
-        || peer_id.0.starts_with(b"xUTB")
+pub(crate) fn check_bitcomet(peer_id: [u8; 20]) -> Option<Client> {
+    if peer_id.starts_with(b"exbc") || peer_id.starts_with(b"FUTB") || peer_id.starts_with(b"xUTB")
     {
-        return if &peer_id.0[6..10] == b"LORD" {
+        return if &peer_id[6..10] == b"LORD" {
             Some(Client::BitLord)
         } else {
             Some(Client::BitComet)
@@ -100,24 +236,62 @@ fn check_bitcomet(peer_id: PeerId) -> Option<Client> {
     None
 }
 
-impl TryFrom<PeerId> for Client {
+// apparently BitSpirit and BitComet have a special spoof mode, which ends peer IDs
+// with one of those markers
+pub(crate) fn has_spoof_marker(peer_id: [u8; 20]) -> bool {
+    peer_id.ends_with(b"UDP0") || peer_id.ends_with(b"HTTPBT")
+}
+
+// The bytes `try_from()` and `parse_version()` look at, for caching: two peer IDs with
+// the same key parse into the same client and version. Only covers Azureus and Shadow
+// styles, for the rest I return None.
+#[cfg(feature = "std")]
+pub(crate) fn cache_key(peer_id: [u8; 20]) -> Option<[u8; 9]> {
+    if has_spoof_marker(peer_id) {
+        return None;
+    }
+
+    let mut key = [0; 9];
+    if Azureus::does_match(peer_id) {
+        key[..8].copy_from_slice(&peer_id[..8]);
+        // BiglyBT marks Android builds with the byte after the header, see Azureus::parse()
+        key[8] = u8::from(peer_id[8] == b'A');
+        return Some(key);
+    }
+    if Shadow::does_match(peer_id) {
+        key[..6].copy_from_slice(&peer_id[..6]);
+        return Some(key);
+    }
+    None
+}
+
+impl TryFrom<[u8; 20]> for Client {
     type Error = ClientParsingError;
 
-    fn try_from(peer_id: PeerId) -> Result<Self, Self::Error> {
-        if peer_id.0.ends_with(b"UDP0") || peer_id.0.ends_with(b"HTTPBT") {
+    fn try_from(peer_id: [u8; 20]) -> Result<Self, Self::Error> {
+        Self::try_from_traced(peer_id, |_| ())
+    }
+}
+
+impl Client {
+    // `try_from()` that reports every check it makes to `trace`, for `parse_explained()`
+    pub(crate) fn try_from_traced(
+        peer_id: [u8; 20],
+        mut trace: impl FnMut(Step),
+    ) -> Result<Self, ClientParsingError> {
+        let spoof_marker = has_spoof_marker(peer_id);
+        trace(Step::SpoofMarker(spoof_marker));
+        if spoof_marker {
             // apparently BitSpirit and BitComet have a special spoof mode, so I need
             // to check for their special encodings, lest I misidentify them further down
-            if let Some(client) = check_bitspirit(peer_id) {
-                return Ok(client);
-            }
-            if let Some(client) = check_bitcomet(peer_id) {
-                return Ok(client);
-            }
-
-            return Ok(Self::PossibleBitSpirit);
+            return Ok(
+                check_bitspirit_bitcomet(peer_id, &mut trace).unwrap_or(Self::PossibleBitSpirit)
+            );
         }
 
-        if Azureus::does_match(peer_id) {
+        let azureus = Azureus::does_match(peer_id);
+        trace(Step::Azureus(azureus));
+        if azureus {
             let client = Azureus::parse(peer_id)?;
 
             // webtorrent (and apparently A LOT of code going decade+ back, see
@@ -130,24 +304,99 @@ impl TryFrom<PeerId> for Client {
             return Ok(Self::AzureusStyle(client));
         }
 
-        if Shadow::does_match(peer_id) {
+        let shadow = Shadow::does_match(peer_id);
+        trace(Step::Shadow(shadow));
+        if shadow {
             let client = Shadow::parse(peer_id)?;
             return Ok(Self::ShadowStyle(client));
         }
 
-        if Mainline::does_match(peer_id) {
+        let mainline = Mainline::does_match(peer_id);
+        trace(Step::Mainline(mainline));
+        if mainline {
             let client = Mainline::parse(peer_id)?;
             return Ok(Self::MainlineStyle(client));
         }
 
         // bitspirit/bitcomet check outside of their spoof mode
-        if let Some(client) = check_bitspirit(peer_id) {
+        if let Some(client) = check_bitspirit_bitcomet(peer_id, &mut trace) {
             return Ok(client);
         }
-        if let Some(client) = check_bitcomet(peer_id) {
-            return Ok(client);
+
+        let substring = Substring::matches(peer_id).next();
+        trace(Step::Substring(
+            substring.map(|(_, substr, offset)| (substr, offset)),
+        ));
+        substring
+            .map(|(client, ..)| Self::Substring(client))
+            .ok_or(ClientParsingError::UnknownClient)
+    }
+}
+
+fn check_bitspirit_bitcomet(peer_id: [u8; 20], trace: &mut impl FnMut(Step)) -> Option<Client> {
+    let bitspirit = check_bitspirit(peer_id);
+    trace(Step::BitSpirit(bitspirit.is_some()));
+    if bitspirit.is_some() {
+        return bitspirit;
+    }
+
+    let bitcomet = check_bitcomet(peer_id);
+    trace(Step::BitComet(bitcomet.is_some()));
+    bitcomet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_styles::azureus::version_styles::StyleKind;
+    use crate::client_styles::mainline::MainlineVersion;
+    use crate::client_styles::shadow::ShadowVersion;
+    use proptest::prelude::*;
+
+    fn representable(client: Client) -> BoxedStrategy<Option<Version>> {
+        match client {
+            Client::AzureusStyle(x) => x.version_style().representable(),
+            Client::ShadowStyle(_) => prop_oneof![
+                Just(None),
+                ShadowVersion::representable().prop_map(|v| Some(Version::Shadow(v)))
+            ]
+            .boxed(),
+            Client::MainlineStyle(_) => MainlineVersion::representable()
+                .prop_map(|v| Some(Version::Mainline(v)))
+                .boxed(),
+            _ => unreachable!("only structured styles are encodable"),
         }
+    }
+
+    fn client_and_version() -> impl Strategy<Value = (KnownClient, Option<Version>)> {
+        let encodable = Client::all_encodable()
+            .filter(|c| match c {
+                Client::AzureusStyle(x) => {
+                    !matches!(x.version_style(), StyleKind::Unknown | StyleKind::NoVersion)
+                }
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+        prop::sample::select(encodable)
+            .prop_flat_map(|c| (Just(c.to_canonical()), representable(c)))
+    }
 
-        Substring::parse(peer_id).map(Self::Substring)
+    // lowercase only, so that the random tail can't accidentally contain BitSpirit/BitComet
+    // spoof markers or the BiglyBT Android marker
+    fn random() -> impl Strategy<Value = [u8; 20]> {
+        prop::array::uniform20(prop::sample::select(
+            b"0123456789abcdefghijklmnopqrstuvwxyz".to_vec(),
+        ))
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip((client, version) in client_and_version(), random in random()) {
+            let peer_id = Client::encode(client, version.as_ref(), random).unwrap();
+
+            let parsed = Client::try_from(peer_id).unwrap();
+            prop_assert_eq!(parsed.to_canonical(), client);
+            prop_assert_eq!(parsed.parse_version(peer_id).unwrap(), version);
+        }
     }
 }
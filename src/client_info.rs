@@ -0,0 +1,195 @@
+// metadata is generated from registry.toml. It's best effort: I only fill in what I could
+// verify, the rest stays `None`, so please send a PR if you know more about a client
+
+use crate::known_clients::KnownClient;
+use core::fmt;
+
+/// Operating systems and environments a client runs on.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Linux,
+    Bsd,
+    Android,
+    Ios,
+    /// Runs in a web browser.
+    Web,
+}
+
+/// Metadata about a [`KnownClient`], see [`KnownClient::info`].
+///
+/// Fields are `None` (or empty) when the information isn't known.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ClientInfo {
+    /// Official website or source repository.
+    pub homepage: Option<&'static str>,
+    /// Whether the client is still maintained.
+    pub maintained: Option<bool>,
+    /// Year of the first known release.
+    pub first_release: Option<u16>,
+    /// Year of the last known release. `None` for maintained clients.
+    pub last_release: Option<u16>,
+    /// Platforms the client was released for.
+    pub platforms: &'static [Platform],
+    /// SPDX identifier of the open-source license, `None` for proprietary clients.
+    pub license: Option<&'static str>,
+    /// The BitTorrent engine the client is built on, `None` for clients that implement
+    /// the protocol themselves. Engines never have an engine of their own.
+    pub engine: Option<KnownClient>,
+}
+
+include!(concat!(env!("OUT_DIR"), "/codegen_client_info.rs"));
+
+impl KnownClient {
+    /// Metadata about the client: homepage, maintenance status, platforms, license and
+    /// the engine it's built on.
+    ///
+    /// ```
+    /// use tdyne_peer_id_registry::KnownClient;
+    ///
+    /// let info = KnownClient::QBittorrent.info();
+    /// assert_eq!(info.engine, Some(KnownClient::LibtorrentRasterbar));
+    /// assert_eq!(info.maintained, Some(true));
+    /// ```
+    pub fn info(self) -> ClientInfo {
+        self.codegen_info()
+    }
+}
+
+/// Group of clients that share an engine, for rolling up statistics. See
+/// [`KnownClient::family`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Family {
+    /// [`KnownClient::LibtorrentRasterbar`] and the clients built on it, e.g. qBittorrent
+    /// and Deluge.
+    Libtorrent,
+    /// µTorrent in all its editions, and BitTorrent since 6.0.
+    UTorrent,
+    /// Azureus and its successors, Vuze and BiglyBT.
+    Azureus,
+    /// WebTorrent and the apps built on it.
+    WebTorrent,
+}
+
+impl Family {
+    /// Stable machine identifier of the family: lowercase ASCII letters. Like
+    /// [`KnownClient::id`], identifiers never change once published.
+    ///
+    /// ```
+    /// use tdyne_peer_id_registry::Family;
+    ///
+    /// assert_eq!(Family::UTorrent.id(), "utorrent");
+    /// ```
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Libtorrent => "libtorrent",
+            Self::UTorrent => "utorrent",
+            Self::Azureus => "azureus",
+            Self::WebTorrent => "webtorrent",
+        }
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let repr = match self {
+            Self::Libtorrent => "libtorrent (Rasterbar)",
+            Self::UTorrent => "\u{00B5}Torrent",
+            Self::Azureus => "Azureus",
+            Self::WebTorrent => "WebTorrent",
+        };
+        write!(f, "{repr}")
+    }
+}
+
+impl KnownClient {
+    /// The family the client belongs to, derived from its [engine](ClientInfo::engine).
+    /// `None` for clients that don't share an engine with any other known client.
+    ///
+    /// ```
+    /// use tdyne_peer_id_registry::{Family, KnownClient};
+    ///
+    /// assert_eq!(KnownClient::Deluge.family(), Some(Family::Libtorrent));
+    /// assert_eq!(KnownClient::LibtorrentRasterbar.family(), Some(Family::Libtorrent));
+    /// assert_eq!(KnownClient::Transmission.family(), None);
+    /// ```
+    pub fn family(self) -> Option<Family> {
+        match self.info().engine.unwrap_or(self) {
+            Self::LibtorrentRasterbar => Some(Family::Libtorrent),
+            Self::UTorrent => Some(Family::UTorrent),
+            Self::Azureus => Some(Family::Azureus),
+            Self::WebTorrent => Some(Family::WebTorrent),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(KnownClient::QBittorrent, Some(KnownClient::LibtorrentRasterbar))]
+    #[test_case(KnownClient::Deluge, Some(KnownClient::LibtorrentRasterbar))]
+    #[test_case(KnownClient::BiglyBtAndroid, Some(KnownClient::Azureus))]
+    #[test_case(KnownClient::Transmission, None)]
+    #[test_case(KnownClient::BitBuddy, None)]
+    fn test_engine(client: KnownClient, engine: Option<KnownClient>) {
+        assert_eq!(client.info().engine, engine);
+    }
+
+    #[test_case(KnownClient::LibtorrentRasterbar, Some(Family::Libtorrent))]
+    #[test_case(KnownClient::QBittorrent, Some(Family::Libtorrent))]
+    #[test_case(KnownClient::Halite, Some(Family::Libtorrent))]
+    #[test_case(KnownClient::UTorrentEmbedded, Some(Family::UTorrent))]
+    #[test_case(KnownClient::BitTorrent, Some(Family::UTorrent))]
+    #[test_case(KnownClient::Vuze, Some(Family::Azureus))]
+    #[test_case(KnownClient::BiglyBtAndroid, Some(Family::Azureus))]
+    #[test_case(KnownClient::WebTorrentDesktop, Some(Family::WebTorrent))]
+    #[test_case(KnownClient::Transmission, None)]
+    #[test_case(KnownClient::Mainline, None)]
+    fn test_family(client: KnownClient, family: Option<Family>) {
+        assert_eq!(client.family(), family);
+    }
+
+    #[test_case(Family::Libtorrent, "libtorrent")]
+    #[test_case(Family::UTorrent, "utorrent")]
+    #[test_case(Family::Azureus, "azureus")]
+    #[test_case(Family::WebTorrent, "webtorrent")]
+    fn test_family_id(family: Family, id: &str) {
+        assert_eq!(family.id(), id);
+    }
+
+    #[test]
+    fn test_engines_are_roots() {
+        for client in KnownClient::all() {
+            if let Some(engine) = client.info().engine {
+                assert_eq!(engine.info().engine, None, "{client:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency() {
+        for client in KnownClient::all() {
+            let info = client.info();
+            if let (Some(first), Some(last)) = (info.first_release, info.last_release) {
+                assert!(first <= last, "{client:?}");
+            }
+            if info.maintained == Some(true) {
+                assert_eq!(info.last_release, None, "{client:?}");
+            }
+            if let Some(homepage) = info.homepage {
+                assert!(homepage.starts_with("https://"), "{client:?}");
+            }
+        }
+    }
+}
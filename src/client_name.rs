@@ -0,0 +1,229 @@
+// clients announce their name and version in the `v` key of the BEP 10 extension handshake
+// and in the User-Agent of HTTP announces, e.g. `qBittorrent/4.6.2`, this maps them onto
+// the same clients the peer ID parser returns
+
+use crate::client_info::Family;
+use crate::errors::UnknownClientName;
+use crate::known_clients::KnownClient;
+use crate::Parsed;
+use std::borrow::Cow;
+
+/// Client and version announced in the `v` key of a
+/// [BEP 10](https://www.bittorrent.org/beps/bep_0010.html) extension handshake or in the
+/// `User-Agent` header of an HTTP announce, returned by [`parse_client_name`] and
+/// [`parse_user_agent`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParsedClientName {
+    /// The recognised client, the same one [`parse_structured`](crate::parse_structured)
+    /// returns for the client's peer IDs.
+    pub client: KnownClient,
+    /// Version as announced, without the leading `v` some clients add. `None` if the client
+    /// didn't announce one.
+    pub version: Option<String>,
+}
+
+impl From<ParsedClientName> for Parsed {
+    fn from(value: ParsedClientName) -> Self {
+        Self {
+            client: value.client.to_string(),
+            version: Ok(value.version),
+        }
+    }
+}
+
+/// Parses the client name and version from the `v` key of a BEP 10 extension handshake.
+///
+/// The name is matched against the [`Display`](std::fmt::Display) names and the
+/// [aliases](KnownClient::aliases) of the known clients, ignoring case if there is no exact
+/// match. The version is the first word after the name, separated by a space or a slash.
+/// The key is supposed to be UTF-8, but older µTorrent versions send Latin-1, so
+/// anything that isn't valid UTF-8 is read as Latin-1.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::{parse_client_name, KnownClient};
+///
+/// let parsed = parse_client_name("qBittorrent/4.6.2").unwrap();
+/// assert_eq!(parsed.client, KnownClient::QBittorrent);
+/// assert_eq!(parsed.version.as_deref(), Some("4.6.2"));
+///
+/// let parsed = parse_client_name(b"\xb5Torrent 1.8.2").unwrap();
+/// assert_eq!(parsed.client, KnownClient::UTorrent);
+/// ```
+pub fn parse_client_name(v: impl AsRef<[u8]>) -> Result<ParsedClientName, UnknownClientName> {
+    let v = decode(v.as_ref());
+    let (name, version) = split(&v);
+
+    Ok(ParsedClientName {
+        client: KnownClient::from_name(name).ok_or(UnknownClientName)?,
+        version: version.map(str::to_string),
+    })
+}
+
+/// Parses the client name and version from the `User-Agent` header of an HTTP announce,
+/// e.g. `Transmission/4.0.4` or `uTorrent/3550(46096)`.
+///
+/// Names are matched the same way as in [`parse_client_name`]. Versions are converted into
+/// the ones the peer ID parser returns for the same client: µTorrent and its relatives write
+/// the version as four digits without dots (`3550` for 3.5.5), and rTorrent sends both its
+/// own version and the version of libTorrent, which is the one in its peer IDs, so rTorrent
+/// agents without the latter have no version. Versions that don't follow these formats are
+/// returned as announced.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::{parse_user_agent, KnownClient};
+///
+/// let parsed = parse_user_agent("uTorrent/3550(46096)").unwrap();
+/// assert_eq!(parsed.client, KnownClient::UTorrent);
+/// assert_eq!(parsed.version.as_deref(), Some("3.5.5"));
+///
+/// let parsed = parse_user_agent("rtorrent/0.9.8/0.13.8").unwrap();
+/// assert_eq!(parsed.client, KnownClient::LibTorrentRakshasa);
+/// assert_eq!(parsed.version.as_deref(), Some("0.13.8"));
+/// ```
+pub fn parse_user_agent(ua: impl AsRef<[u8]>) -> Result<ParsedClientName, UnknownClientName> {
+    let ua = decode(ua.as_ref());
+    let (name, version) = split(&ua);
+    let client = KnownClient::from_name(name).ok_or(UnknownClientName)?;
+
+    let version = match version {
+        // rTorrent sends its own version and libTorrent's (`rtorrent/0.9.8/0.13.8`), but
+        // its peer IDs only have libTorrent's. Without libTorrent's, there is nothing to return
+        Some(_)
+            if client == KnownClient::LibTorrentRakshasa
+                && name.to_ascii_lowercase().contains("rtorrent") =>
+        {
+            match ua.trim().split('/').skip(1).collect::<Vec<_>>()[..] {
+                [_, libtorrent] if libtorrent.starts_with(|c: char| c.is_ascii_digit()) => {
+                    Some(libtorrent.to_string())
+                }
+                _ => None,
+            }
+        }
+        Some(v) if client.family() == Some(Family::UTorrent) && is_undotted(v) => Some(undotted(v)),
+        v => v.map(str::to_string),
+    };
+    Ok(ParsedClientName { client, version })
+}
+
+// µTorrent's `3550` is 3.5.5, the last digit is always zero. Anything else isn't in that
+// format, and is left as is rather than guessed at
+fn is_undotted(version: &str) -> bool {
+    version.len() == 4 && version.bytes().all(|b| b.is_ascii_digit()) && version.ends_with('0')
+}
+
+fn undotted(version: &str) -> String {
+    version[..3]
+        .chars()
+        .map(String::from)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn decode(v: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(v) {
+        Ok(s) => Cow::Borrowed(s),
+        Err(_) => Cow::Owned(v.iter().copied().map(char::from).collect()),
+    }
+}
+
+// the version starts at the first space or slash that is followed by a digit, optionally
+// prefixed with `v`, so that names with spaces (`libTorrent (Rakshasa) / rTorrent`) stay whole.
+// It ends at the next space, slash or parenthesis, e.g. `0.9.8/0.13.8` or `3550(46096)`
+fn split(v: &str) -> (&str, Option<&str>) {
+    let v = v.trim();
+    for (i, c) in v.char_indices() {
+        if c != ' ' && c != '/' {
+            continue;
+        }
+        let rest = &v[i + 1..];
+        let rest = rest.strip_prefix('v').unwrap_or(rest);
+        if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '/' || c == '(')
+                .unwrap_or(rest.len());
+            return (v[..i].trim_end(), Some(&rest[..end]));
+        }
+    }
+    (v, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use pretty_assertions::assert_eq;
+    use tdyne_peer_id::PeerId;
+    use test_case::test_case;
+
+    #[test_case(b"qBittorrent/4.6.2", KnownClient::QBittorrent, Some("4.6.2"))]
+    #[test_case(b"Transmission 4.0.4", KnownClient::Transmission, Some("4.0.4"))]
+    #[test_case(
+        b"libTorrent (Rakshasa) / rTorrent 0.9.8",
+        KnownClient::LibTorrentRakshasa,
+        Some("0.9.8")
+    )]
+    #[test_case(b"rTorrent 0.9.8", KnownClient::LibTorrentRakshasa, Some("0.9.8"))]
+    #[test_case(b"libTorrent 0.13.8", KnownClient::LibTorrentRakshasa, Some("0.13.8"))]
+    #[test_case(b"libtorrent/2.0.9", KnownClient::LibtorrentRasterbar, Some("2.0.9"))]
+    #[test_case(b"Deluge 2.1.1", KnownClient::Deluge, Some("2.1.1"))]
+    #[test_case(b"BiglyBT 3.5.0.0", KnownClient::BiglyBt, Some("3.5.0.0"))]
+    #[test_case(b"Tixati v3.19", KnownClient::Tixati, Some("3.19"))]
+    #[test_case("\u{00B5}Torrent 3.5.5".as_bytes(), KnownClient::UTorrent, Some("3.5.5"))]
+    #[test_case(b"\xb5Torrent 1.8.2", KnownClient::UTorrent, Some("1.8.2"))]
+    #[test_case(b"uTorrent Mac 1.5.11", KnownClient::UTorrentMac, Some("1.5.11"))]
+    #[test_case(
+        b"transmission 2.94 (d8e60ee44f)",
+        KnownClient::Transmission,
+        Some("2.94")
+    )]
+    #[test_case(b"KTorrent", KnownClient::KTorrent, None)]
+    fn test_parse_client_name(v: &[u8], client: KnownClient, version: Option<&str>) {
+        let parsed = parse_client_name(v).unwrap();
+        assert_eq!(parsed.client, client);
+        assert_eq!(parsed.version.as_deref(), version);
+    }
+
+    #[test_case(b"qBittorrent/4.5.2", KnownClient::QBittorrent, Some("4.5.2"))]
+    #[test_case(b"Transmission/4.0.4", KnownClient::Transmission, Some("4.0.4"))]
+    #[test_case(b"uTorrent/3550(46096)", KnownClient::UTorrent, Some("3.5.5"))]
+    #[test_case(b"uTorrentMac/1870(43796)", KnownClient::UTorrentMac, Some("1.8.7"))]
+    #[test_case(b"BitTorrent/7100(255)", KnownClient::BitTorrent, Some("7.1.0"))]
+    #[test_case(b"uTorrent/31010", KnownClient::UTorrent, Some("31010"))]
+    #[test_case(b"uTorrent/355", KnownClient::UTorrent, Some("355"))]
+    #[test_case(b"uTorrent/3551", KnownClient::UTorrent, Some("3551"))]
+    #[test_case(b"uTorrent/3.5.5", KnownClient::UTorrent, Some("3.5.5"))]
+    #[test_case(b"Deluge 2.1.1", KnownClient::Deluge, Some("2.1.1"))]
+    #[test_case(
+        b"rtorrent/0.9.8/0.13.8",
+        KnownClient::LibTorrentRakshasa,
+        Some("0.13.8")
+    )]
+    #[test_case(b"rtorrent/0.9.8", KnownClient::LibTorrentRakshasa, None)]
+    #[test_case(b"libTorrent/0.13.8", KnownClient::LibTorrentRakshasa, Some("0.13.8"))]
+    fn test_parse_user_agent(ua: &[u8], client: KnownClient, version: Option<&str>) {
+        let parsed = parse_user_agent(ua).unwrap();
+        assert_eq!(parsed.client, client);
+        assert_eq!(parsed.version.as_deref(), version);
+    }
+
+    #[test_case(b"")]
+    #[test_case(b"Not A Client 1.0")]
+    #[test_case(b"4.6.2")]
+    fn test_unknown(v: &[u8]) {
+        assert_eq!(parse_client_name(v), Err(UnknownClientName));
+    }
+
+    #[test]
+    fn test_same_as_peer_id() {
+        let from_v = Parsed::from(parse_client_name("qBittorrent/4.6.2").unwrap());
+        let from_peer_id = parse(PeerId::from(b"-qB4620-xxxxxxxxxxxx")).unwrap();
+        assert_eq!(from_v.client, from_peer_id.client);
+        assert_eq!(from_v.version, from_peer_id.version);
+    }
+}
@@ -1,5 +1,45 @@
+use core::fmt;
+
 pub(crate) mod azureus;
 pub(crate) mod mainline;
 pub(crate) mod shadow;
 pub(crate) mod substring;
 pub(crate) mod types;
+
+/// Peer ID encoding convention that the parser recognised.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Style {
+    /// `-` followed by a two-byte client tag, four version bytes and `-`, e.g. `-TR4040-`.
+    Azureus,
+    /// One-byte client tag followed by up to four version bytes padded with `-`,
+    /// e.g. `T03A--`.
+    Shadow,
+    /// One-byte client tag followed by dash-separated decimal numbers, e.g. `M4-20-8-`.
+    Mainline,
+    /// No structure, the client is recognised by a known substring at a fixed offset.
+    Substring,
+    /// BitComet and BitLord's own `exbc`/`FUTB`/`xUTB` prefixes.
+    BitComet,
+    /// BitSpirit's `BS` marker at offset 2.
+    BitSpirit,
+    /// The peer ID ends with a BitSpirit/BitComet spoof mode marker (`UDP0` or `HTTPBT`),
+    /// but the rest of it doesn't match either client.
+    SpoofMarker,
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let repr = match self {
+            Self::Azureus => "Azureus",
+            Self::Shadow => "Shad0w",
+            Self::Mainline => "Mainline",
+            Self::Substring => "substring",
+            Self::BitComet => "BitComet",
+            Self::BitSpirit => "BitSpirit",
+            Self::SpoofMarker => "spoof marker",
+        };
+        write!(f, "{repr}")
+    }
+}
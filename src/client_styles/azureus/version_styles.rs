@@ -5,7 +5,12 @@ use crate::client_styles::azureus::version_styles::three_base16_alpha_beta::Thre
 use crate::client_styles::azureus::version_styles::transmission::Transmission;
 use crate::client_styles::azureus::version_styles::two_base16::TwoBase16;
 use crate::client_styles::azureus::version_styles::two_maj_two_min_base10::TwoMajTwoMinBase10;
-use std::fmt;
+use crate::errors::{EncodingError, UnknownVersionStyle, VersionParsingError};
+use crate::version::Version;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::ops::Range;
+use core::str::FromStr;
 
 pub(crate) mod four_base16;
 pub(crate) mod one_base10_unknown;
@@ -15,19 +20,26 @@ pub(crate) mod transmission;
 pub(crate) mod two_base16;
 pub(crate) mod two_maj_two_min_base10;
 
+/// Version formats used by Azureus-style clients, the bytes between the client tag and
+/// the closing dash.
 #[non_exhaustive]
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum Styles {
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Styles {
     // I note correspondence to Webtorrent's parsers (that in turn come from much older code)
     // in comments below
     // VER_AZ_THREE_DIGITS
     // VER_AZ_DELUGE is the same and just allows base16
+    /// See [`ThreeBase16`].
     ThreeBase16(ThreeBase16),
     // VER_AZ_THREE_DIGITS_PLUS_MNEMONIC
+    /// See [`ThreeBase16AlphaBeta`].
     ThreeBase16AlphaBeta(ThreeBase16AlphaBeta),
     // VER_AZ_FOUR_DIGITS
+    /// See [`FourBase16`].
     FourBase16(FourBase16),
     // VER_AZ_TWO_MAJ_TWO_MIN
+    /// See [`TwoMajTwoMinBase10`].
     TwoMajTwoMinBase10(TwoMajTwoMinBase10),
     // VER_AZ_SKIP_FIRST_ONE_MAJ_TWO_MIN
     // - this is just TwoMajTwoMinBase10, webtorrent skips leading zeros for some reason
@@ -38,6 +50,7 @@ pub(crate) enum Styles {
     //   https://github.com/KDE/libktorrent/blob/30adde89afcc73c0018eb30844b82faae1703dd1/src/version.cpp#L24
     //   I can use three_base_16 instead
     // VER_AZ_TRANSMISSION_STYLE
+    /// See [`Transmission`].
     Transmission(Transmission),
     // VER_AZ_WEBTORRENT_STYLE
     // -  VER_AZ_WEBTORRENT_STYLE seems identical to VER_AZ_TWO_MAJ_TWO_MIN, except
@@ -52,10 +65,149 @@ pub(crate) enum Styles {
     //   (and those lines' history)
     //   it seems that both actually use 3 hex digits
     // VER_NONE is handled with an upstream Option
+    /// See [`OneBase10Unknown`].
     OneBase10Unknown(OneBase10Unknown),
+    /// See [`TwoBase16`].
     TwoBase16(TwoBase16),
 }
 
+/// Which of the [`Styles`] a client uses, without the values. Also covers clients that don't
+/// encode a version and clients whose version encoding isn't known yet.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StyleKind {
+    /// See [`ThreeBase16`].
+    ThreeBase16,
+    /// See [`ThreeBase16AlphaBeta`].
+    ThreeBase16AlphaBeta,
+    /// See [`FourBase16`].
+    FourBase16,
+    /// See [`TwoMajTwoMinBase10`].
+    TwoMajTwoMinBase10,
+    /// See [`Transmission`].
+    Transmission,
+    /// See [`OneBase10Unknown`].
+    OneBase10Unknown,
+    /// See [`TwoBase16`].
+    TwoBase16,
+    /// The client encodes a version, but its format isn't known, parsed as
+    /// [`Version::Unknown`].
+    Unknown,
+    /// The client doesn't encode a version.
+    NoVersion,
+}
+
+impl StyleKind {
+    pub(crate) fn parse(self, peer_id: [u8; 20]) -> Result<Option<Version>, VersionParsingError> {
+        let v = match self {
+            Self::ThreeBase16 => Styles::ThreeBase16(ThreeBase16::try_from(peer_id)?),
+            Self::ThreeBase16AlphaBeta => {
+                Styles::ThreeBase16AlphaBeta(ThreeBase16AlphaBeta::try_from(peer_id)?)
+            }
+            Self::FourBase16 => Styles::FourBase16(FourBase16::try_from(peer_id)?),
+            Self::TwoMajTwoMinBase10 => {
+                Styles::TwoMajTwoMinBase10(TwoMajTwoMinBase10::try_from(peer_id)?)
+            }
+            Self::Transmission => Styles::Transmission(Transmission::try_from(peer_id)?),
+            Self::OneBase10Unknown => {
+                Styles::OneBase10Unknown(OneBase10Unknown::try_from(peer_id)?)
+            }
+            Self::TwoBase16 => Styles::TwoBase16(TwoBase16::try_from(peer_id)?),
+            Self::Unknown => return Ok(Some(Version::Unknown)),
+            Self::NoVersion => return Ok(None),
+        };
+        Ok(Some(Version::Azureus(v)))
+    }
+
+    // the header bytes `parse()` decodes, None if it doesn't decode any
+    #[cfg(feature = "std")]
+    pub(crate) fn version_bytes(self) -> Option<Range<usize>> {
+        match self {
+            Self::OneBase10Unknown => Some(3..4),
+            Self::TwoBase16 => Some(3..5),
+            Self::ThreeBase16 => Some(3..6),
+            Self::ThreeBase16AlphaBeta
+            | Self::FourBase16
+            | Self::TwoMajTwoMinBase10
+            | Self::Transmission => Some(3..7),
+            Self::Unknown | Self::NoVersion => None,
+        }
+    }
+}
+
+/// Parses the variant name, e.g. `"ThreeBase16"`, the same names `registry.toml` uses.
+impl FromStr for StyleKind {
+    type Err = UnknownVersionStyle;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ThreeBase16" => Ok(Self::ThreeBase16),
+            "ThreeBase16AlphaBeta" => Ok(Self::ThreeBase16AlphaBeta),
+            "FourBase16" => Ok(Self::FourBase16),
+            "TwoMajTwoMinBase10" => Ok(Self::TwoMajTwoMinBase10),
+            "Transmission" => Ok(Self::Transmission),
+            "OneBase10Unknown" => Ok(Self::OneBase10Unknown),
+            "TwoBase16" => Ok(Self::TwoBase16),
+            "Unknown" => Ok(Self::Unknown),
+            "NoVersion" => Ok(Self::NoVersion),
+            _ => Err(UnknownVersionStyle),
+        }
+    }
+}
+
+#[cfg(test)]
+impl StyleKind {
+    // every version of this style that can be encoded, for property tests
+    pub(crate) fn representable(self) -> proptest::strategy::BoxedStrategy<Option<Version>> {
+        use proptest::prelude::*;
+
+        fn boxed<T: Into<Styles> + fmt::Debug>(
+            s: impl Strategy<Value = T> + 'static,
+        ) -> BoxedStrategy<Option<Version>> {
+            s.prop_map(|x| Some(Version::Azureus(x.into()))).boxed()
+        }
+
+        match self {
+            Self::ThreeBase16 => boxed(ThreeBase16::representable()),
+            Self::ThreeBase16AlphaBeta => boxed(ThreeBase16AlphaBeta::representable()),
+            Self::FourBase16 => boxed(FourBase16::representable()),
+            Self::TwoMajTwoMinBase10 => boxed(TwoMajTwoMinBase10::representable()),
+            Self::Transmission => boxed(Transmission::representable()),
+            Self::OneBase10Unknown => boxed(OneBase10Unknown::representable()),
+            Self::TwoBase16 => boxed(TwoBase16::representable()),
+            Self::Unknown | Self::NoVersion => Just(None).boxed(),
+        }
+    }
+}
+
+impl Styles {
+    pub(crate) fn kind(&self) -> StyleKind {
+        match self {
+            Self::ThreeBase16(_) => StyleKind::ThreeBase16,
+            Self::ThreeBase16AlphaBeta(_) => StyleKind::ThreeBase16AlphaBeta,
+            Self::FourBase16(_) => StyleKind::FourBase16,
+            Self::TwoMajTwoMinBase10(_) => StyleKind::TwoMajTwoMinBase10,
+            Self::Transmission(_) => StyleKind::Transmission,
+            Self::OneBase10Unknown(_) => StyleKind::OneBase10Unknown,
+            Self::TwoBase16(_) => StyleKind::TwoBase16,
+        }
+    }
+
+    // writes the version into bytes 3..7 of an Azureus-style peer ID
+    pub(crate) fn encode(&self, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        match self {
+            Self::ThreeBase16(x) => x.encode(bytes),
+            Self::ThreeBase16AlphaBeta(x) => x.encode(bytes),
+            Self::FourBase16(x) => x.encode(bytes),
+            Self::TwoMajTwoMinBase10(x) => x.encode(bytes),
+            Self::Transmission(x) => x.encode(bytes),
+            Self::OneBase10Unknown(x) => x.encode(bytes),
+            Self::TwoBase16(x) => x.encode(bytes),
+        }
+    }
+}
+
 impl fmt::Display for Styles {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -69,3 +221,19 @@ impl fmt::Display for Styles {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::client_styles::azureus::Azureus;
+    use pretty_assertions::assert_eq;
+
+    // build.rs writes registry.toml's style names out as `StyleKind` paths, so the compiler
+    // checks them, this keeps `from_str()`, which overlays go through, on the same names
+    #[test]
+    fn test_registry_styles_round_trip_from_str() {
+        for client in Azureus::all() {
+            let style = client.version_style();
+            assert_eq!(format!("{style:?}").parse(), Ok(style), "{client:?}");
+        }
+    }
+}
@@ -1,10 +1,11 @@
-use crate::errors::VersionParsingError;
-use crate::version_utils::base16;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use crate::errors::{EncodingError, VersionParsingError};
+use crate::version_utils::{base16, to_base16};
+use core::fmt;
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct FourBase16(pub u8, pub u8, pub u8, pub u8);
+/// Four base 16 digits, e.g. `-AZ5770-` for Vuze 5.7.7.0.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FourBase16(pub u8, pub u8, pub u8, pub u8);
 
 impl fmt::Display for FourBase16 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -12,11 +13,11 @@ impl fmt::Display for FourBase16 {
     }
 }
 
-impl TryFrom<PeerId> for FourBase16 {
+impl TryFrom<[u8; 20]> for FourBase16 {
     type Error = VersionParsingError;
 
-    fn try_from(peer_id: PeerId) -> Result<Self, Self::Error> {
-        let [_dash, _id1, _id2, b1, b2, b3, b4, ..] = peer_id.0;
+    fn try_from(peer_id: [u8; 20]) -> Result<Self, Self::Error> {
+        let [_dash, _id1, _id2, b1, b2, b3, b4, ..] = peer_id;
         Ok(FourBase16(
             base16(b1)?,
             base16(b2)?,
@@ -26,15 +27,69 @@ impl TryFrom<PeerId> for FourBase16 {
     }
 }
 
+impl FourBase16 {
+    pub(crate) fn encode(&self, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        bytes[3..7].copy_from_slice(&[
+            to_base16(self.0)?,
+            to_base16(self.1)?,
+            to_base16(self.2)?,
+            to_base16(self.3)?,
+        ]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl FourBase16 {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        (0u8..16, 0u8..16, 0u8..16, 0u8..16).prop_map(|(v1, v2, v3, v4)| Self(v1, v2, v3, v4))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
 
     #[test]
     fn test_basic() {
-        let peer_id = PeerId::from(b"-XX10AF-xxxxxxxxxxxx");
+        let peer_id = *b"-XX10AF-xxxxxxxxxxxx";
         let s = FourBase16::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "1.0.10.15");
     }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+        FourBase16(1, 0, 10, 15).encode(&mut bytes).unwrap();
+        assert_eq!(&bytes, b"-XX10AF-xxxxxxxxxxxx");
+
+        assert_eq!(
+            FourBase16(1, 0, 16, 15).encode(&mut bytes).unwrap_err(),
+            EncodingError::ComponentOutOfRange(16)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(version in FourBase16::representable()) {
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            version.encode(&mut bytes).unwrap();
+            prop_assert_eq!(FourBase16::try_from(bytes).unwrap(), version);
+        }
+
+        #[test]
+        fn test_encode_never_lies(v1: u8, v2: u8, v3: u8, v4: u8) {
+            let version = FourBase16(v1, v2, v3, v4);
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            match version.encode(&mut bytes) {
+                Ok(()) => prop_assert_eq!(FourBase16::try_from(bytes).unwrap(), version),
+                Err(e) => prop_assert!(matches!(e, EncodingError::ComponentOutOfRange(16..))),
+            }
+        }
+    }
 }
@@ -1,10 +1,12 @@
-use crate::errors::VersionParsingError;
-use crate::version_utils::base10;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use crate::errors::{EncodingError, VersionParsingError};
+use crate::version_utils::{base10, to_base10};
+use core::fmt;
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct OneBase10Unknown(pub u8);
+/// A single base 10 major version, the rest of the version bytes aren't decoded yet.
+/// Displayed as `5.x`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OneBase10Unknown(pub u8);
 
 impl fmt::Display for OneBase10Unknown {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -12,33 +14,78 @@ impl fmt::Display for OneBase10Unknown {
     }
 }
 
-impl TryFrom<PeerId> for OneBase10Unknown {
+impl TryFrom<[u8; 20]> for OneBase10Unknown {
     type Error = VersionParsingError;
 
-    fn try_from(peer_id: PeerId) -> Result<Self, Self::Error> {
-        let [_dash, _id1, _id2, b1, ..] = peer_id.0;
+    fn try_from(peer_id: [u8; 20]) -> Result<Self, Self::Error> {
+        let [_dash, _id1, _id2, b1, ..] = peer_id;
 
         Ok(OneBase10Unknown(base10(b1)?))
     }
 }
 
+impl OneBase10Unknown {
+    pub(crate) fn encode(&self, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        // the meaning of the remaining bytes is unknown, so I zero them
+        bytes[3..7].copy_from_slice(&[to_base10(self.0)?, b'0', b'0', b'0']);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl OneBase10Unknown {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        (0u8..10).prop_map(Self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
 
     #[test]
     fn test_basic() {
-        let peer_id = PeerId::from(b"-FL51FF-xxxxxxxxxxxx");
+        let peer_id = *b"-FL51FF-xxxxxxxxxxxx";
         let s = OneBase10Unknown::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "5.x");
 
-        let peer_id = PeerId::from(b"-FL10xx-xxxxxxxxxxxx");
+        let peer_id = *b"-FL10xx-xxxxxxxxxxxx";
         let s = OneBase10Unknown::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "1.x");
 
-        let peer_id = PeerId::from(b"-MG21xx-xxxxxxxxxxxx");
+        let peer_id = *b"-MG21xx-xxxxxxxxxxxx";
         let s = OneBase10Unknown::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "2.x");
     }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes = *b"-FL-----xxxxxxxxxxxx";
+        OneBase10Unknown(5).encode(&mut bytes).unwrap();
+        assert_eq!(&bytes, b"-FL5000-xxxxxxxxxxxx");
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(version in OneBase10Unknown::representable()) {
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            version.encode(&mut bytes).unwrap();
+            prop_assert_eq!(OneBase10Unknown::try_from(bytes).unwrap(), version);
+        }
+
+        #[test]
+        fn test_encode_never_lies(v1: u8) {
+            let version = OneBase10Unknown(v1);
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            match version.encode(&mut bytes) {
+                Ok(()) => prop_assert_eq!(OneBase10Unknown::try_from(bytes).unwrap(), version),
+                Err(e) => prop_assert!(matches!(e, EncodingError::ComponentOutOfRange(10..))),
+            }
+        }
+    }
 }
@@ -1,11 +1,12 @@
-use crate::errors::VersionParsingError;
+use crate::errors::{EncodingError, VersionParsingError};
 
-use crate::version_utils::base16;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use crate::version_utils::{base16, to_base16};
+use core::fmt;
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct ThreeBase16(pub u8, pub u8, pub u8);
+/// Three base 16 digits, e.g. `-qB4630-` for qBittorrent 4.6.3.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreeBase16(pub u8, pub u8, pub u8);
 
 impl fmt::Display for ThreeBase16 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -13,24 +14,73 @@ impl fmt::Display for ThreeBase16 {
     }
 }
 
-impl TryFrom<PeerId> for ThreeBase16 {
+impl TryFrom<[u8; 20]> for ThreeBase16 {
     type Error = VersionParsingError;
 
-    fn try_from(peer_id: PeerId) -> Result<Self, Self::Error> {
-        let [_dash, _id1, _id2, b1, b2, b3, ..] = peer_id.0;
+    fn try_from(peer_id: [u8; 20]) -> Result<Self, Self::Error> {
+        let [_dash, _id1, _id2, b1, b2, b3, ..] = peer_id;
         Ok(ThreeBase16(base16(b1)?, base16(b2)?, base16(b3)?))
     }
 }
 
+impl ThreeBase16 {
+    pub(crate) fn encode(&self, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        bytes[3..7].copy_from_slice(&[
+            to_base16(self.0)?,
+            to_base16(self.1)?,
+            to_base16(self.2)?,
+            b'0',
+        ]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl ThreeBase16 {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        (0u8..16, 0u8..16, 0u8..16).prop_map(|(v1, v2, v3)| Self(v1, v2, v3))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
 
     #[test]
     fn test_basic() {
-        let peer_id = PeerId::from(b"-XX1AF0-xxxxxxxxxxxx");
+        let peer_id = *b"-XX1AF0-xxxxxxxxxxxx";
         let s = ThreeBase16::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "1.10.15");
     }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+        ThreeBase16(1, 10, 15).encode(&mut bytes).unwrap();
+        assert_eq!(&bytes, b"-XX1AF0-xxxxxxxxxxxx");
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(version in ThreeBase16::representable()) {
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            version.encode(&mut bytes).unwrap();
+            prop_assert_eq!(ThreeBase16::try_from(bytes).unwrap(), version);
+        }
+
+        #[test]
+        fn test_encode_never_lies(v1: u8, v2: u8, v3: u8) {
+            let version = ThreeBase16(v1, v2, v3);
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            match version.encode(&mut bytes) {
+                Ok(()) => prop_assert_eq!(ThreeBase16::try_from(bytes).unwrap(), version),
+                Err(e) => prop_assert!(matches!(e, EncodingError::ComponentOutOfRange(16..))),
+            }
+        }
+    }
 }
@@ -1,18 +1,24 @@
-use crate::errors::VersionParsingError;
+use crate::errors::{EncodingError, VersionParsingError};
 
 use crate::client_styles::azureus::version_styles::three_base16::ThreeBase16;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use core::fmt;
 
+/// Release channel mnemonic used by [`ThreeBase16AlphaBeta`].
 #[non_exhaustive]
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum Suffix {
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Suffix {
+    /// Encoded as `A`.
     Alpha,
+    /// Encoded as `B`.
     Beta,
 }
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct ThreeBase16AlphaBeta(pub u8, pub u8, pub u8, pub Option<Suffix>);
+/// Three base 16 digits followed by an optional alpha/beta mnemonic, e.g. `-UT355B-` for
+/// µTorrent 3.5.5 (Beta).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreeBase16AlphaBeta(pub u8, pub u8, pub u8, pub Option<Suffix>);
 
 impl fmt::Display for ThreeBase16AlphaBeta {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -26,11 +32,11 @@ impl fmt::Display for ThreeBase16AlphaBeta {
     }
 }
 
-impl TryFrom<PeerId> for ThreeBase16AlphaBeta {
+impl TryFrom<[u8; 20]> for ThreeBase16AlphaBeta {
     type Error = VersionParsingError;
 
-    fn try_from(peer_id: PeerId) -> Result<Self, Self::Error> {
-        let [_dash, _id1, _id2, _b1, _b2, _b3, letter, ..] = peer_id.0;
+    fn try_from(peer_id: [u8; 20]) -> Result<Self, Self::Error> {
+        let [_dash, _id1, _id2, _b1, _b2, _b3, letter, ..] = peer_id;
 
         let base = ThreeBase16::try_from(peer_id)?;
 
@@ -44,19 +50,81 @@ impl TryFrom<PeerId> for ThreeBase16AlphaBeta {
     }
 }
 
+impl ThreeBase16AlphaBeta {
+    pub(crate) fn encode(&self, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        ThreeBase16(self.0, self.1, self.2).encode(bytes)?;
+        bytes[6] = match self.3 {
+            Some(Suffix::Alpha) => b'A',
+            Some(Suffix::Beta) => b'B',
+            None => b'0',
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl ThreeBase16AlphaBeta {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        let suffix = prop_oneof![
+            Just(None),
+            Just(Some(Suffix::Alpha)),
+            Just(Some(Suffix::Beta))
+        ];
+        (ThreeBase16::representable(), suffix)
+            .prop_map(|(ThreeBase16(v1, v2, v3), s)| Self(v1, v2, v3, s))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
 
     #[test]
     fn test_basic() {
-        let peer_id = PeerId::from(b"-XX1AFA-xxxxxxxxxxxx");
+        let peer_id = *b"-XX1AFA-xxxxxxxxxxxx";
         let s = ThreeBase16AlphaBeta::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "1.10.15 (Alpha)");
 
-        let peer_id = PeerId::from(b"-XX1AF0-xxxxxxxxxxxx");
+        let peer_id = *b"-XX1AF0-xxxxxxxxxxxx";
         let s = ThreeBase16AlphaBeta::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "1.10.15");
     }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+        ThreeBase16AlphaBeta(1, 10, 15, Some(Suffix::Beta))
+            .encode(&mut bytes)
+            .unwrap();
+        assert_eq!(&bytes, b"-XX1AFB-xxxxxxxxxxxx");
+
+        ThreeBase16AlphaBeta(3, 5, 5, None)
+            .encode(&mut bytes)
+            .unwrap();
+        assert_eq!(&bytes, b"-XX3550-xxxxxxxxxxxx");
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(version in ThreeBase16AlphaBeta::representable()) {
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            version.encode(&mut bytes).unwrap();
+            prop_assert_eq!(ThreeBase16AlphaBeta::try_from(bytes).unwrap(), version);
+        }
+
+        #[test]
+        fn test_encode_never_lies(v1: u8, v2: u8, v3: u8, alpha: bool) {
+            let version = ThreeBase16AlphaBeta(v1, v2, v3, alpha.then_some(Suffix::Alpha));
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            match version.encode(&mut bytes) {
+                Ok(()) => prop_assert_eq!(ThreeBase16AlphaBeta::try_from(bytes).unwrap(), version),
+                Err(e) => prop_assert!(matches!(e, EncodingError::ComponentOutOfRange(16..))),
+            }
+        }
+    }
 }
@@ -1,12 +1,15 @@
-use crate::errors::VersionParsingError;
-use crate::version_utils::{base10, base62};
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use crate::errors::{EncodingError, VersionParsingError};
+use crate::version_utils::{base10, base62, to_base10, to_base62, to_two_base10};
+use core::fmt;
 
+/// Release type encoded in the last version byte of Transmission peer IDs.
 #[non_exhaustive]
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum Suffix {
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Suffix {
+    /// Encoded as `X` before 3.0 and as `B` since.
     Beta,
+    /// Encoded as `Z`.
     Dev,
 }
 
@@ -19,11 +22,17 @@ impl fmt::Display for Suffix {
     }
 }
 
+/// Transmission changed its version encoding twice, each variant covers one of the eras.
 #[non_exhaustive]
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum Transmission {
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Transmission {
+    /// `0.x` versions, encoded as `-TR00xx-` with two base 10 digits.
     Pre1(u8),
+    /// `1.x` and `2.x` versions, encoded as one base 10 digit for the major version,
+    /// two base 10 digits for the minor version and a release type byte.
     Pre3(u8, u8, Option<Suffix>),
+    /// Versions since `3.0`, encoded as three base 62 digits and a release type byte.
     Current(u8, u8, u8, Option<Suffix>),
 }
 
@@ -51,11 +60,11 @@ impl fmt::Display for Transmission {
     }
 }
 
-impl TryFrom<PeerId> for Transmission {
+impl TryFrom<[u8; 20]> for Transmission {
     type Error = VersionParsingError;
 
-    fn try_from(peer_id: PeerId) -> Result<Self, Self::Error> {
-        let [_dash, _id1, _id2, b1, b2, b3, b4, ..] = peer_id.0;
+    fn try_from(peer_id: [u8; 20]) -> Result<Self, Self::Error> {
+        let [_dash, _id1, _id2, b1, b2, b3, b4, ..] = peer_id;
 
         // Transmission parses its own peer ID here:
         // https://github.com/transmission/transmission/blob/ce39b01dd2dfa1352730035c75ef2b6771c8ace2/libtransmission/clients.cc#L418
@@ -88,10 +97,72 @@ impl TryFrom<PeerId> for Transmission {
     }
 }
 
+impl Transmission {
+    pub(crate) fn encode(&self, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        let header = match *self {
+            Self::Pre1(v) => {
+                let [b3, b4] = to_two_base10(v)?;
+                [b'0', b'0', b3, b4]
+            }
+            Self::Pre3(v1, v2, maybe_s) => {
+                // `00` in the first two bytes is reserved for Pre1, and anything from `3`
+                // onwards is parsed as Current
+                if v1 >= 3 || (v1 == 0 && v2 < 10) {
+                    return Err(EncodingError::UnrepresentableVersion);
+                }
+                let [b2, b3] = to_two_base10(v2)?;
+                let b4 = match maybe_s {
+                    Some(Suffix::Beta) => b'X',
+                    Some(Suffix::Dev) => b'Z',
+                    None => b'0',
+                };
+                [to_base10(v1)?, b2, b3, b4]
+            }
+            Self::Current(v1, v2, v3, maybe_s) => {
+                if v1 < 3 {
+                    return Err(EncodingError::UnrepresentableVersion);
+                }
+                let b4 = match maybe_s {
+                    Some(Suffix::Beta) => b'B',
+                    Some(Suffix::Dev) => b'Z',
+                    None => b'0',
+                };
+                [to_base62(v1)?, to_base62(v2)?, to_base62(v3)?, b4]
+            }
+        };
+        bytes[3..7].copy_from_slice(&header);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Transmission {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        let suffix = || {
+            prop_oneof![
+                Just(None),
+                Just(Some(Suffix::Beta)),
+                Just(Some(Suffix::Dev))
+            ]
+        };
+        prop_oneof![
+            (0u8..100).prop_map(Self::Pre1),
+            (1u8..3, 0u8..100, suffix()).prop_map(|(v1, v2, s)| Self::Pre3(v1, v2, s)),
+            (10u8..100, suffix()).prop_map(|(v2, s)| Self::Pre3(0, v2, s)),
+            (3u8..62, 0u8..62, 0u8..62, suffix())
+                .prop_map(|(v1, v2, v3, s)| Self::Current(v1, v2, v3, s)),
+        ]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
     use test_case::test_case;
 
     #[test_case(b"-TR0006-xxxxxxxxxxxx", "0.6")]
@@ -103,8 +174,62 @@ mod tests {
     #[test_case(b"-TR404Z-xxxxxxxxxxxx", "4.0.4 (Dev)")]
     #[test_case(b"-TR404B-xxxxxxxxxxxx", "4.0.4 (Beta)")]
     fn test_version(peer_id_bytes: &[u8], version: &str) {
-        let peer_id = PeerId::try_from(peer_id_bytes).unwrap();
+        let peer_id: [u8; 20] = peer_id_bytes.try_into().unwrap();
         let s = Transmission::try_from(peer_id).unwrap().to_string();
         assert_eq!(&s, version);
     }
+
+    #[test_case(Transmission::Pre1(6), b"-TR0006-xxxxxxxxxxxx")]
+    #[test_case(Transmission::Pre1(72), b"-TR0072-xxxxxxxxxxxx")]
+    #[test_case(Transmission::Pre3(1, 11, Some(Suffix::Dev)), b"-TR111Z-xxxxxxxxxxxx")]
+    #[test_case(Transmission::Pre3(1, 33, Some(Suffix::Beta)), b"-TR133X-xxxxxxxxxxxx")]
+    #[test_case(Transmission::Pre3(2, 94, None), b"-TR2940-xxxxxxxxxxxx")]
+    #[test_case(Transmission::Current(4, 0, 4, None), b"-TR4040-xxxxxxxxxxxx")]
+    #[test_case(
+        Transmission::Current(4, 0, 4, Some(Suffix::Dev)),
+        b"-TR404Z-xxxxxxxxxxxx"
+    )]
+    #[test_case(
+        Transmission::Current(4, 0, 4, Some(Suffix::Beta)),
+        b"-TR404B-xxxxxxxxxxxx"
+    )]
+    fn test_encode(version: Transmission, peer_id_bytes: &[u8; 20]) {
+        let mut bytes = *b"-TR-----xxxxxxxxxxxx";
+        version.encode(&mut bytes).unwrap();
+        assert_eq!(&bytes, peer_id_bytes);
+    }
+
+    #[test_case(Transmission::Pre3(3, 0, None))]
+    #[test_case(Transmission::Pre3(0, 9, None))]
+    #[test_case(Transmission::Current(2, 94, 0, None))]
+    fn test_encode_unrepresentable(version: Transmission) {
+        let mut bytes = [0; 20];
+        assert_eq!(
+            version.encode(&mut bytes).unwrap_err(),
+            EncodingError::UnrepresentableVersion
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(version in Transmission::representable()) {
+            let mut bytes = *b"-TR-----xxxxxxxxxxxx";
+            version.encode(&mut bytes).unwrap();
+            prop_assert_eq!(Transmission::try_from(bytes).unwrap(), version);
+        }
+
+        #[test]
+        fn test_encode_never_lies(v1: u8, v2: u8, v3: u8, current: bool) {
+            let version = if current {
+                Transmission::Current(v1, v2, v3, None)
+            } else {
+                Transmission::Pre3(v1, v2, None)
+            };
+            let mut bytes = *b"-TR-----xxxxxxxxxxxx";
+            match version.encode(&mut bytes) {
+                Ok(()) => prop_assert_eq!(Transmission::try_from(bytes).unwrap(), version),
+                Err(e) => prop_assert!(matches!(e, EncodingError::ComponentOutOfRange(_) | EncodingError::UnrepresentableVersion)),
+            }
+        }
+    }
 }
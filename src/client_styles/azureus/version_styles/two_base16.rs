@@ -1,11 +1,12 @@
-use crate::errors::VersionParsingError;
+use crate::errors::{EncodingError, VersionParsingError};
 
-use crate::version_utils::base16;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use crate::version_utils::{base16, to_base16};
+use core::fmt;
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct TwoBase16(pub u8, pub u8);
+/// Two base 16 digits, e.g. `-FD51` for Free Download Manager 5.1.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TwoBase16(pub u8, pub u8);
 
 impl fmt::Display for TwoBase16 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -13,24 +14,68 @@ impl fmt::Display for TwoBase16 {
     }
 }
 
-impl TryFrom<PeerId> for TwoBase16 {
+impl TryFrom<[u8; 20]> for TwoBase16 {
     type Error = VersionParsingError;
 
-    fn try_from(peer_id: PeerId) -> Result<Self, Self::Error> {
-        let [_dash, _id1, _id2, b1, b2, ..] = peer_id.0;
+    fn try_from(peer_id: [u8; 20]) -> Result<Self, Self::Error> {
+        let [_dash, _id1, _id2, b1, b2, ..] = peer_id;
         Ok(TwoBase16(base16(b1)?, base16(b2)?))
     }
 }
 
+impl TwoBase16 {
+    pub(crate) fn encode(&self, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        bytes[3..7].copy_from_slice(&[to_base16(self.0)?, to_base16(self.1)?, b'0', b'0']);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl TwoBase16 {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        (0u8..16, 0u8..16).prop_map(|(v1, v2)| Self(v1, v2))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
 
     #[test]
     fn test_basic() {
-        let peer_id = PeerId::from(b"-XX5111-xxxxxxxxxxxx");
+        let peer_id = *b"-XX5111-xxxxxxxxxxxx";
         let s = TwoBase16::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "5.1");
     }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+        TwoBase16(5, 1).encode(&mut bytes).unwrap();
+        assert_eq!(&bytes, b"-XX5100-xxxxxxxxxxxx");
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(version in TwoBase16::representable()) {
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            version.encode(&mut bytes).unwrap();
+            prop_assert_eq!(TwoBase16::try_from(bytes).unwrap(), version);
+        }
+
+        #[test]
+        fn test_encode_never_lies(v1: u8, v2: u8) {
+            let version = TwoBase16(v1, v2);
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            match version.encode(&mut bytes) {
+                Ok(()) => prop_assert_eq!(TwoBase16::try_from(bytes).unwrap(), version),
+                Err(e) => prop_assert!(matches!(e, EncodingError::ComponentOutOfRange(16..))),
+            }
+        }
+    }
 }
@@ -1,16 +1,18 @@
-use crate::errors::VersionParsingError;
-use crate::version_utils::base10;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use crate::errors::{EncodingError, VersionParsingError};
+use crate::version_utils::{base10, to_two_base10};
+use core::fmt;
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct TwoMajTwoMinBase10(pub u8, pub u8);
+/// Two base 10 digits for the major version and two for the minor, e.g. `-WW0125-` for
+/// WebTorrent 1.25.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TwoMajTwoMinBase10(pub u8, pub u8);
 
-impl TryFrom<PeerId> for TwoMajTwoMinBase10 {
+impl TryFrom<[u8; 20]> for TwoMajTwoMinBase10 {
     type Error = VersionParsingError;
 
-    fn try_from(peer_id: PeerId) -> Result<Self, Self::Error> {
-        let [_dash, _id1, _id2, b11, b12, b21, b22, ..] = peer_id.0;
+    fn try_from(peer_id: [u8; 20]) -> Result<Self, Self::Error> {
+        let [_dash, _id1, _id2, b11, b12, b21, b22, ..] = peer_id;
         Ok(TwoMajTwoMinBase10(
             base10(b11)? * 10 + base10(b12)?,
             base10(b21)? * 10 + base10(b22)?,
@@ -18,21 +20,72 @@ impl TryFrom<PeerId> for TwoMajTwoMinBase10 {
     }
 }
 
+impl TwoMajTwoMinBase10 {
+    pub(crate) fn encode(&self, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        let [b11, b12] = to_two_base10(self.0)?;
+        let [b21, b22] = to_two_base10(self.1)?;
+        bytes[3..7].copy_from_slice(&[b11, b12, b21, b22]);
+        Ok(())
+    }
+}
+
 impl fmt::Display for TwoMajTwoMinBase10 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}.{}", self.0, self.1)
     }
 }
 
+#[cfg(test)]
+impl TwoMajTwoMinBase10 {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        (0u8..100, 0u8..100).prop_map(|(v1, v2)| Self(v1, v2))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
 
     #[test]
     fn test_basic() {
-        let peer_id = PeerId::from(b"-XX0125-xxxxxxxxxxxx");
+        let peer_id = *b"-XX0125-xxxxxxxxxxxx";
         let s = TwoMajTwoMinBase10::try_from(peer_id).unwrap().to_string();
         assert_eq!(s, "1.25");
     }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+        TwoMajTwoMinBase10(1, 25).encode(&mut bytes).unwrap();
+        assert_eq!(&bytes, b"-XX0125-xxxxxxxxxxxx");
+
+        assert_eq!(
+            TwoMajTwoMinBase10(100, 25).encode(&mut bytes).unwrap_err(),
+            EncodingError::ComponentOutOfRange(100)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(version in TwoMajTwoMinBase10::representable()) {
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            version.encode(&mut bytes).unwrap();
+            prop_assert_eq!(TwoMajTwoMinBase10::try_from(bytes).unwrap(), version);
+        }
+
+        #[test]
+        fn test_encode_never_lies(v1: u8, v2: u8) {
+            let version = TwoMajTwoMinBase10(v1, v2);
+            let mut bytes = *b"-XX-----xxxxxxxxxxxx";
+            match version.encode(&mut bytes) {
+                Ok(()) => prop_assert_eq!(TwoMajTwoMinBase10::try_from(bytes).unwrap(), version),
+                Err(e) => prop_assert!(matches!(e, EncodingError::ComponentOutOfRange(100..))),
+            }
+        }
+    }
 }
@@ -1,13 +1,14 @@
 use crate::client_styles::types::ClientStyle;
-use crate::errors::{ClientParsingError, VersionParsingError};
+use crate::errors::{ClientParsingError, EncodingError, VersionParsingError};
 use crate::known_clients::KnownClient;
 use crate::version::Version;
 use crate::version_utils::base10;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::ops::Range;
 
 #[non_exhaustive]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub(crate) enum Mainline {
     #[allow(clippy::enum_variant_names)]
     Mainline,
@@ -15,24 +16,41 @@ pub(crate) enum Mainline {
     AmazonAWSS3,
 }
 
+impl Mainline {
+    // the bytes `parse_version()` decodes: up to the third dash or to the end
+    #[cfg(feature = "std")]
+    pub(crate) fn version_bytes(peer_id: [u8; 20]) -> Range<usize> {
+        let start = if peer_id[0] == b'S' { 3 } else { 1 };
+        let end = (start..peer_id.len())
+            .filter(|&i| peer_id[i] == b'-')
+            .nth(2)
+            .unwrap_or(peer_id.len());
+        start..end
+    }
+
+    pub(crate) fn all() -> impl Iterator<Item = Self> {
+        [Self::Mainline, Self::QueenBee, Self::AmazonAWSS3].into_iter()
+    }
+}
+
 impl ClientStyle for Mainline {
-    fn does_match(peer_id: PeerId) -> bool {
+    fn does_match(peer_id: [u8; 20]) -> bool {
         // it's a relatively rare style and is not very well defined, so I just try to match
         // known clients directly
-        match peer_id.0 {
+        match peer_id {
             [b'M' | b'Q', b1, ..] if b1.is_ascii_digit() => true,
             [b'S', b'3', b'-', b1, ..] if b1.is_ascii_digit() => true,
             _ => false,
         }
     }
 
-    fn parse(peer_id: PeerId) -> Result<Self, ClientParsingError> {
-        match peer_id.0 {
+    fn parse(peer_id: [u8; 20]) -> Result<Self, ClientParsingError> {
+        match peer_id {
             [b'M', b1, ..] if b1.is_ascii_digit() => Ok(Self::Mainline),
             [b'Q', b1, ..] if b1.is_ascii_digit() => Ok(Self::QueenBee),
             [b'S', b'3', b'-', b1, ..] if b1.is_ascii_digit() => Ok(Self::AmazonAWSS3),
             _ => {
-                let mainline_prefix = &peer_id.0[0..4];
+                let mainline_prefix = &peer_id[0..4];
                 Err(ClientParsingError::UnknownMainlineClientPrefix(
                     mainline_prefix.try_into().unwrap(),
                 ))
@@ -40,11 +58,11 @@ impl ClientStyle for Mainline {
         }
     }
 
-    fn parse_version(self, peer_id: PeerId) -> Result<Option<Version>, VersionParsingError> {
+    fn parse_version(self, peer_id: [u8; 20]) -> Result<Option<Version>, VersionParsingError> {
         // this should be guaranteed in the parse above
-        assert!(peer_id.0[1].is_ascii_digit());
+        assert!(peer_id[1].is_ascii_digit());
 
-        let start = if peer_id.0[0] == b'S' { 3 } else { 1 };
+        let start = if peer_id[0] == b'S' { 3 } else { 1 };
 
         let mut v1 = 0u8;
         let mut v2 = 0u8;
@@ -53,8 +71,8 @@ impl ClientStyle for Mainline {
         let vs = [&mut v1, &mut v2, &mut v3];
         let mut current_v_idx = 0;
 
-        for i in start..peer_id.0.len() {
-            match peer_id.0[i] {
+        for &b in &peer_id[start..] {
+            match b {
                 b'-' => {
                     current_v_idx += 1;
                     if current_v_idx == vs.len() {
@@ -74,6 +92,41 @@ impl ClientStyle for Mainline {
         Ok(Some(Version::Mainline(MainlineVersion(v1, v2, v3))))
     }
 
+    fn encode(self, version: Option<&Version>, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        let Some(Version::Mainline(MainlineVersion(v1, v2, v3))) = version else {
+            return Err(EncodingError::MismatchedVersionStyle);
+        };
+
+        let prefix: &[u8] = match self {
+            Self::Mainline => b"M",
+            Self::QueenBee => b"Q",
+            Self::AmazonAWSS3 => b"S3-",
+        };
+
+        // the longest possible header is `S3-255-255-255-`, so it always fits. The header
+        // is prefilled with dashes, so only the digits are written
+        let mut header = [b'-'; 20];
+        header[..prefix.len()].copy_from_slice(prefix);
+        let mut written = prefix.len();
+        for v in [*v1, *v2, *v3] {
+            if v >= 100 {
+                header[written] = b'0' + v / 100;
+                written += 1;
+            }
+            if v >= 10 {
+                header[written] = b'0' + v / 10 % 10;
+                written += 1;
+            }
+            header[written] = b'0' + v % 10;
+            written += 2;
+        }
+
+        // clients pad the header with dashes up to 8 bytes
+        let len = written.max(8);
+        bytes[..len].copy_from_slice(&header[..len]);
+        Ok(())
+    }
+
     fn to_canonical(self) -> KnownClient {
         match self {
             Self::Mainline => KnownClient::Mainline,
@@ -83,10 +136,19 @@ impl ClientStyle for Mainline {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Mainline-style version: three base 10 numbers separated by dashes, e.g. `M4-20-8-`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MainlineVersion(pub u8, pub u8, pub u8);
 
+impl MainlineVersion {
+    /// Creates a version from its three components.
+    pub fn new(v1: u8, v2: u8, v3: u8) -> Self {
+        Self(v1, v2, v3)
+    }
+}
+
 impl fmt::Display for MainlineVersion {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let Self(v1, v2, v3) = self;
@@ -94,10 +156,21 @@ impl fmt::Display for MainlineVersion {
     }
 }
 
+#[cfg(test)]
+impl MainlineVersion {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        any::<(u8, u8, u8)>().prop_map(|(v1, v2, v3)| Self(v1, v2, v3))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
     use test_case::test_case;
 
     #[test_case(b"M4-3-6--xxxxxxxxxxxx", "4.3.6")]
@@ -106,7 +179,7 @@ mod tests {
     #[test_case(b"M4-20-120-xxxxxxxxxx", "4.20.120")]
     #[test_case(b"S3-1-0-0--0123456789", "1.0.0")]
     fn test_mainline_version(peer_id_bytes: &[u8; 20], version: &str) {
-        let peer_id = PeerId::from(peer_id_bytes);
+        let peer_id = *peer_id_bytes;
         assert_eq!(
             Mainline::Mainline
                 .parse_version(peer_id)
@@ -116,4 +189,40 @@ mod tests {
             version.to_string()
         );
     }
+
+    #[test_case(Mainline::Mainline, MainlineVersion(4, 3, 6), b"M4-3-6--xxxxxxxxxxxx")]
+    #[test_case(
+        Mainline::Mainline,
+        MainlineVersion(4, 20, 120),
+        b"M4-20-120-xxxxxxxxxx"
+    )]
+    #[test_case(Mainline::QueenBee, MainlineVersion(1, 2, 3), b"Q1-2-3--xxxxxxxxxxxx")]
+    #[test_case(
+        Mainline::AmazonAWSS3,
+        MainlineVersion(1, 0, 0),
+        b"S3-1-0-0-xxxxxxxxxxx"
+    )]
+    fn test_encode(client: Mainline, version: MainlineVersion, peer_id_bytes: &[u8; 20]) {
+        let mut bytes = *b"xxxxxxxxxxxxxxxxxxxx";
+        client
+            .encode(Some(&Version::Mainline(version)), &mut bytes)
+            .unwrap();
+        assert_eq!(&bytes, peer_id_bytes);
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(
+            client in prop::sample::select(Mainline::all().collect::<Vec<_>>()),
+            version in MainlineVersion::representable(),
+        ) {
+            let version = Some(Version::Mainline(version));
+            let mut bytes = *b"xxxxxxxxxxxxxxxxxxxx";
+            client.encode(version.as_ref(), &mut bytes).unwrap();
+
+            let peer_id = bytes;
+            prop_assert!(Mainline::does_match(peer_id));
+            prop_assert_eq!(client.parse_version(peer_id).unwrap(), version);
+        }
+    }
 }
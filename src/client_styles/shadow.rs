@@ -1,57 +1,48 @@
 use crate::client_styles::types::ClientStyle;
-use crate::errors::{ClientParsingError, VersionParsingError};
+use crate::errors::{ClientParsingError, EncodingError, VersionParsingError};
 use crate::known_clients::KnownClient;
 use crate::version::Version;
-use crate::version_utils::base62;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use crate::version_utils::{base62, to_base62};
+use core::fmt;
 
-#[non_exhaustive]
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum Shadow {
-    Abc,
-    OspreyPermaseed,
-    BTQueue,
-    Tribler,
-    Shad0w,
-    BitTornado,
-    UPnPNat,
-}
-
-// you can find the list of tags in build.rs
-include!(concat!(env!("OUT_DIR"), "/codegen_tags_shadow.rs"));
+// the enum and the tags are generated from registry.toml by build.rs
+include!(concat!(env!("OUT_DIR"), "/codegen_shadow.rs"));
 
 fn is_base62(c: u8) -> bool {
     matches!(c, b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z')
 }
 
-impl ClientStyle for Shadow {
-    fn does_match(peer_id: PeerId) -> bool {
-        // Here I deviate form webtorrent. I check that the first letter is a known tag,
-        // that the first six symbols ("header" in the description) are a (possibly empty)
-        // sequence of base64 symbols ending in dashes. Unlike mainline encoding, Shadow
-        // shouldn't have symbols after dashes.
-        //
-        // I tried to filter the tag by just an uppercase ASCII, but it then captures stuff
-        // like `Pando-`, which does fit the pattern.
-        if !TAGS.contains_key(&peer_id.0[0]) {
-            return false;
-        }
+impl Shadow {
+    // every tag maps to a distinct client, so no need to pick between several
+    fn tag(self) -> Option<u8> {
+        TAGS.entries().find(|(_, v)| **v == self).map(|(k, _)| *k)
+    }
+
+    pub(crate) fn all() -> impl Iterator<Item = Self> {
+        ALL.iter().copied()
+    }
+
+    // checks everything but the tag, so that the runtime registry can bring its own tags
+    pub(crate) fn has_header(peer_id: [u8; 20]) -> bool {
+        // Here I deviate form webtorrent. I check that the first six symbols ("header" in
+        // the description) are a tag followed by a (possibly empty) sequence of base64
+        // symbols ending in dashes. Unlike mainline encoding, Shadow shouldn't have symbols
+        // after dashes.
 
         // TopBT does match the criteria, but it's not really a shadow-style client
-        if &peer_id.0[0..=5] == b"TB100-" {
+        if &peer_id[0..=5] == b"TB100-" {
             return false;
         }
 
         let mut padding_started = false;
 
-        if peer_id.0[5] != b'-' {
+        if peer_id[5] != b'-' {
             return false;
         }
 
         // I use base62 instead of the original base64 because - has two meanings, and both
         // . and - should be fairly rare in version numbers (how many projects reach v63?)
-        for b in &peer_id.0[1..5] {
+        for b in &peer_id[1..5] {
             match b {
                 b'-' if padding_started => (),
                 b'-' if !padding_started => padding_started = true,
@@ -64,15 +55,11 @@ impl ClientStyle for Shadow {
         true
     }
 
-    fn parse(peer_id: PeerId) -> Result<Self, ClientParsingError> {
-        let prefix = peer_id.0[0];
-        TAGS.get(&prefix)
-            .copied()
-            .ok_or(ClientParsingError::UnknownShadowStylePrefix(prefix))
-    }
-
-    fn parse_version(self, peer_id: PeerId) -> Result<Option<Version>, VersionParsingError> {
-        let [_tag, b1, b2, b3, b4, ..] = peer_id.0;
+    // the version encoding is the same for all Shadow-style clients
+    pub(crate) fn parse_any_version(
+        peer_id: [u8; 20],
+    ) -> Result<Option<Version>, VersionParsingError> {
+        let [_tag, b1, b2, b3, b4, ..] = peer_id;
 
         if b1 == b'-' {
             return Ok(None);
@@ -98,28 +85,85 @@ impl ClientStyle for Shadow {
 
         Ok(Some(Version::Shadow(ShadowVersion(v1, rest_vs))))
     }
+}
+
+impl ClientStyle for Shadow {
+    fn does_match(peer_id: [u8; 20]) -> bool {
+        // I tried to filter the tag by just an uppercase ASCII, but it then captures stuff
+        // like `Pando-`, which does fit the pattern. So only known tags count.
+        TAGS.contains_key(&peer_id[0]) && Self::has_header(peer_id)
+    }
+
+    fn parse(peer_id: [u8; 20]) -> Result<Self, ClientParsingError> {
+        let prefix = peer_id[0];
+        TAGS.get(&prefix)
+            .copied()
+            .ok_or(ClientParsingError::UnknownShadowStylePrefix(prefix))
+    }
+
+    fn parse_version(self, peer_id: [u8; 20]) -> Result<Option<Version>, VersionParsingError> {
+        Self::parse_any_version(peer_id)
+    }
+
+    fn encode(self, version: Option<&Version>, bytes: &mut [u8; 20]) -> Result<(), EncodingError> {
+        let header = match version {
+            None => *b"-----",
+            Some(Version::Shadow(ShadowVersion(v1, rest_vs))) => {
+                let mut header = *b"-----";
+                header[0] = to_base62(*v1)?;
+
+                let mut padding_started = false;
+                for (b, v) in header[1..4].iter_mut().zip(rest_vs) {
+                    match v {
+                        None => padding_started = true,
+                        // does_match() rejects digits after the padding
+                        Some(_) if padding_started => {
+                            return Err(EncodingError::UnrepresentableVersion)
+                        }
+                        Some(v) => *b = to_base62(*v)?,
+                    }
+                }
+
+                // parse_version() strips the last zero as a release type designator,
+                // so the version has to be passed without it
+                if rest_vs[2] == Some(0) {
+                    return Err(EncodingError::UnrepresentableVersion);
+                }
+
+                header
+            }
+            Some(_) => return Err(EncodingError::MismatchedVersionStyle),
+        };
+
+        bytes[0] = self.tag().ok_or(EncodingError::UnsupportedClient)?;
+        bytes[1..6].copy_from_slice(&header);
+        Ok(())
+    }
 
     fn to_canonical(self) -> KnownClient {
-        use KnownClient as KC;
-
-        match self {
-            Self::Abc => KC::Abc,
-            Self::OspreyPermaseed => KC::OspreyPermaseed,
-            Self::BTQueue => KC::BTQueue,
-            Self::Tribler => KC::Tribler,
-            Self::Shad0w => KC::Shad0w,
-            Self::BitTornado => KC::BitTornado,
-            Self::UPnPNat => KC::UPnPNAT,
-        }
+        self.canonical()
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Shad0w-style version: up to four base 62 digits padded with dashes, e.g. `T03A--` for
+/// BitTornado 0.3.10.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[non_exhaustive]
 // split into a value and array to guarantee that the version is not empty (the entire thing
 // should be None if it is)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShadowVersion(pub u8, pub [Option<u8>; 3]);
 
+impl ShadowVersion {
+    /// Creates a version from the first component and up to three further ones.
+    /// Components after the first `None` are ignored when displaying the version.
+    ///
+    /// The parser drops the fourth component if it's zero, so such versions can't be encoded.
+    pub fn new(v1: u8, rest: [Option<u8>; 3]) -> Self {
+        Self(v1, rest)
+    }
+}
+
 impl fmt::Display for ShadowVersion {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.1 {
@@ -131,10 +175,27 @@ impl fmt::Display for ShadowVersion {
     }
 }
 
+#[cfg(test)]
+impl ShadowVersion {
+    // every version the style can represent, for property tests
+    pub(crate) fn representable() -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+
+        (0u8..62, 0usize..=3, [0u8..62, 0u8..62, 1u8..62]).prop_map(|(v1, len, rest)| {
+            let mut rest_vs = [None; 3];
+            for i in 0..len {
+                rest_vs[i] = Some(rest[i]);
+            }
+            Self(v1, rest_vs)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
     use test_case::test_case;
 
     #[test_case(b"T03A0-xxxxxxxxxxxxxx", true)]
@@ -146,13 +207,13 @@ mod tests {
     #[test_case(b"T0-2--xxxxxxxxxxxxxx", false)]
     #[test_case(b"T0!---xxxxxxxxxxxxxx", false)]
     fn test_match(peer_id_bytes: &[u8; 20], should_match: bool) {
-        let peer_id = PeerId::from(peer_id_bytes);
+        let peer_id = *peer_id_bytes;
         assert_eq!(Shadow::does_match(peer_id), should_match);
     }
 
     #[test]
     fn test_basic() {
-        let peer_id = PeerId::from(b"T03A0-xxxxxxxxxxxxxx");
+        let peer_id = *b"T03A0-xxxxxxxxxxxxxx";
         let s = Shadow::Tribler
             .parse_version(peer_id)
             .unwrap()
@@ -163,7 +224,7 @@ mod tests {
 
     #[test]
     fn test_four() {
-        let peer_id = PeerId::from(b"T03A5-xxxxxxxxxxxxxx");
+        let peer_id = *b"T03A5-xxxxxxxxxxxxxx";
         let s = Shadow::Tribler
             .parse_version(peer_id)
             .unwrap()
@@ -171,4 +232,54 @@ mod tests {
             .to_string();
         assert_eq!(s, "0.3.10.5");
     }
+
+    #[test_case(None, b"T-----xxxxxxxxxxxxxx")]
+    #[test_case(Some(ShadowVersion(0, [None; 3])), b"T0----xxxxxxxxxxxxxx")]
+    #[test_case(Some(ShadowVersion(0, [Some(3), Some(10), None])), b"T03A--xxxxxxxxxxxxxx")]
+    #[test_case(Some(ShadowVersion(3, [Some(1), Some(0), Some(5)])), b"T3105-xxxxxxxxxxxxxx")]
+    fn test_encode(version: Option<ShadowVersion>, peer_id_bytes: &[u8; 20]) {
+        let mut bytes = *b"xxxxxxxxxxxxxxxxxxxx";
+        Shadow::BitTornado
+            .encode(version.map(Version::Shadow).as_ref(), &mut bytes)
+            .unwrap();
+        assert_eq!(&bytes, peer_id_bytes);
+        assert!(Shadow::does_match(bytes));
+    }
+
+    #[test]
+    fn test_encode_trailing_zero() {
+        let version = Version::Shadow(ShadowVersion(0, [Some(3), Some(10), Some(0)]));
+        let mut bytes = [0; 20];
+        assert_eq!(
+            Shadow::BitTornado
+                .encode(Some(&version), &mut bytes)
+                .unwrap_err(),
+            EncodingError::UnrepresentableVersion
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(version in ShadowVersion::representable()) {
+            let version = Some(Version::Shadow(version));
+            let mut bytes = *b"xxxxxxxxxxxxxxxxxxxx";
+            Shadow::Tribler.encode(version.as_ref(), &mut bytes).unwrap();
+
+            let peer_id = bytes;
+            prop_assert!(Shadow::does_match(peer_id));
+            prop_assert_eq!(Shadow::Tribler.parse_version(peer_id).unwrap(), version);
+        }
+    }
+
+    #[test]
+    fn test_encode_gap() {
+        let version = Version::Shadow(ShadowVersion(0, [None, Some(2), None]));
+        let mut bytes = [0; 20];
+        assert_eq!(
+            Shadow::BitTornado
+                .encode(Some(&version), &mut bytes)
+                .unwrap_err(),
+            EncodingError::UnrepresentableVersion
+        );
+    }
 }
@@ -1,12 +1,13 @@
-use crate::errors::{ClientParsingError, VersionParsingError};
+use crate::errors::{ClientParsingError, EncodingError, VersionParsingError};
 use crate::known_clients::KnownClient;
 use crate::version::Version;
-use std::fmt;
-use tdyne_peer_id::PeerId;
+use core::fmt;
 
 pub(crate) trait ClientStyle: fmt::Debug + Copy {
-    fn does_match(peer_id: PeerId) -> bool;
-    fn parse(peer_id: PeerId) -> Result<Self, ClientParsingError>;
-    fn parse_version(self, peer_id: PeerId) -> Result<Option<Version>, VersionParsingError>;
+    fn does_match(peer_id: [u8; 20]) -> bool;
+    fn parse(peer_id: [u8; 20]) -> Result<Self, ClientParsingError>;
+    fn parse_version(self, peer_id: [u8; 20]) -> Result<Option<Version>, VersionParsingError>;
+    // writes the client's header with the version into `bytes`, leaving the rest intact
+    fn encode(self, version: Option<&Version>, bytes: &mut [u8; 20]) -> Result<(), EncodingError>;
     fn to_canonical(self) -> KnownClient;
 }
@@ -0,0 +1,226 @@
+// spoofing clients often send the peer ID of a popular client, but announce their real name
+// in the extension handshake or in the User-Agent, so comparing the two catches them
+
+use crate::client_name::{parse_client_name, parse_user_agent, ParsedClientName};
+use crate::errors::{ClientParsingError, UnknownClientName};
+use crate::known_clients::KnownClient;
+use crate::parse_structured;
+use crate::version::NumericVersion;
+use tdyne_peer_id::PeerId;
+
+/// Whether a peer ID and the client name the peer announces (the `v` key of its extension
+/// handshake or the `User-Agent` of its HTTP announces) agree, returned by [`cross_check`]
+/// and [`cross_check_user_agent`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Verdict {
+    /// Both name the same client, and the versions agree on every component both of them
+    /// have. Versions that can't be compared, e.g. when one side doesn't have a version,
+    /// don't count as a conflict.
+    Consistent,
+    /// The peer ID and the announced name are different clients. Includes the client from
+    /// the peer ID and the announced client.
+    ClientMismatch(KnownClient, KnownClient),
+    /// Both name the same client, but different versions. Includes the version from the
+    /// peer ID and the announced version.
+    VersionMismatch(NumericVersion, NumericVersion),
+    /// The peer ID isn't recognised, so there is nothing to compare.
+    UnknownPeerId(ClientParsingError),
+    /// The announced name isn't a known client, so there is nothing to compare.
+    UnknownClientName,
+}
+
+impl Verdict {
+    /// Whether the peer ID and the announced name conflict, i.e. the verdict is either
+    /// [`Verdict::ClientMismatch`] or [`Verdict::VersionMismatch`].
+    pub fn is_mismatch(&self) -> bool {
+        matches!(self, Self::ClientMismatch(..) | Self::VersionMismatch(..))
+    }
+}
+
+/// Compares the client and the version encoded in a peer ID with the ones announced in
+/// the `v` key of the same peer's [BEP 10](https://www.bittorrent.org/beps/bep_0010.html)
+/// extension handshake.
+///
+/// The peer ID is parsed with [`parse_structured`](crate::parse_structured) and the `v` key
+/// with [`parse_client_name`](crate::parse_client_name). A `v` key that names the engine
+/// of the peer ID's client (e.g. `libtorrent/2.0.9` for a qBittorrent peer ID) agrees with
+/// it, but the versions aren't compared then, since they belong to different projects.
+/// The same goes for rTorrent, whose `v` key has its own version, while its peer IDs have
+/// the version of libTorrent.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::{cross_check, KnownClient, Verdict};
+///
+/// let peer_id = PeerId::from(b"-qB4620-xxxxxxxxxxxx");
+/// assert_eq!(cross_check(peer_id, "qBittorrent/4.6.2"), Verdict::Consistent);
+/// assert_eq!(
+///     cross_check(peer_id, "Transmission 4.0.4"),
+///     Verdict::ClientMismatch(KnownClient::QBittorrent, KnownClient::Transmission)
+/// );
+/// ```
+pub fn cross_check(peer_id: PeerId, v: impl AsRef<[u8]>) -> Verdict {
+    let v = v.as_ref();
+    let mut announced = parse_client_name(v);
+    if let Ok(parsed) = &mut announced {
+        if parsed.client == KnownClient::LibTorrentRakshasa && names_rtorrent(v) {
+            parsed.version = None;
+        }
+    }
+    compare(peer_id, announced)
+}
+
+/// Same as [`cross_check`], but compares the peer ID with the `User-Agent` header of the
+/// HTTP announce that carried it, parsed with [`parse_user_agent`](crate::parse_user_agent).
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::{cross_check_user_agent, KnownClient, Verdict};
+///
+/// let peer_id = PeerId::from(b"-UT355S-xxxxxxxxxxxx");
+/// assert_eq!(cross_check_user_agent(peer_id, "uTorrent/3550(46096)"), Verdict::Consistent);
+/// assert!(cross_check_user_agent(peer_id, "Transmission/4.0.4").is_mismatch());
+/// ```
+pub fn cross_check_user_agent(peer_id: PeerId, user_agent: impl AsRef<[u8]>) -> Verdict {
+    compare(peer_id, parse_user_agent(user_agent))
+}
+
+fn compare(peer_id: PeerId, announced: Result<ParsedClientName, UnknownClientName>) -> Verdict {
+    let from_peer_id = match parse_structured(peer_id) {
+        Ok(parsed) => parsed,
+        Err(e) => return Verdict::UnknownPeerId(e),
+    };
+    let Ok(announced) = announced else {
+        return Verdict::UnknownClientName;
+    };
+
+    if from_peer_id.client != announced.client {
+        return if from_peer_id.client.info().engine == Some(announced.client) {
+            Verdict::Consistent
+        } else {
+            Verdict::ClientMismatch(from_peer_id.client, announced.client)
+        };
+    }
+
+    let peer_id_version = from_peer_id
+        .version
+        .ok()
+        .flatten()
+        .and_then(|v| v.numeric());
+    let announced_version = announced.version.as_deref().and_then(numeric);
+    match (peer_id_version, announced_version) {
+        (Some(a), Some(b)) if !agree(&a, &b) => Verdict::VersionMismatch(a, b),
+        _ => Verdict::Consistent,
+    }
+}
+
+// rTorrent is the front-end of libTorrent (Rakshasa), both map onto the same client,
+// but only libTorrent's version is in the peer ID
+fn names_rtorrent(v: &[u8]) -> bool {
+    v.windows(8).any(|w| w.eq_ignore_ascii_case(b"rtorrent"))
+}
+
+// the leading dot-separated numbers of an announced version, e.g. `4.6.2` of `4.6.2.10`
+fn numeric(version: &str) -> Option<NumericVersion> {
+    let mut components = [0; 4];
+    let mut len = 0;
+    for c in version.split('.').map_while(|c| c.parse().ok()).take(4) {
+        components[len] = c;
+        len += 1;
+    }
+    (len > 0).then(|| NumericVersion::new(&components[..len], None))
+}
+
+// peer IDs often encode fewer components than clients announce (e.g. `OneBase10Unknown`
+// only has the major version), so only the components both have are compared
+fn agree(a: &NumericVersion, b: &NumericVersion) -> bool {
+    let len = a.components().len().min(b.components().len());
+    a.components()[..len] == b.components()[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(b"-qB4620-xxxxxxxxxxxx", b"qBittorrent/4.6.2", Verdict::Consistent)]
+    #[test_case(b"-qB4620-xxxxxxxxxxxx", b"qBittorrent/4.6.2.10", Verdict::Consistent)]
+    #[test_case(b"-qB4620-xxxxxxxxxxxx", b"libtorrent/2.0.9", Verdict::Consistent)]
+    #[test_case(b"-TR4040-xxxxxxxxxxxx", b"Transmission", Verdict::Consistent)]
+    #[test_case(b"-TR2940-xxxxxxxxxxxx", b"Transmission 2.94", Verdict::Consistent)]
+    #[test_case(b"-lt0D80-xxxxxxxxxxxx", b"rTorrent 0.9.8", Verdict::Consistent)]
+    #[test_case(
+        b"-lt0D80-xxxxxxxxxxxx",
+        b"libTorrent (Rakshasa) / rTorrent 0.9.8",
+        Verdict::Consistent
+    )]
+    #[test_case(b"-lt0D80-xxxxxxxxxxxx", b"libTorrent 0.13.8", Verdict::Consistent)]
+    #[test_case(
+        b"-lt0D80-xxxxxxxxxxxx",
+        b"libTorrent 0.12.0",
+        Verdict::VersionMismatch(NumericVersion::new(&[0, 13, 8], None), NumericVersion::new(&[0, 12, 0], None))
+    )]
+    #[test_case(
+        b"-qB4620-xxxxxxxxxxxx",
+        b"qBittorrent/4.5.0",
+        Verdict::VersionMismatch(NumericVersion::new(&[4, 6, 2], None), NumericVersion::new(&[4, 5, 0], None))
+    )]
+    #[test_case(
+        b"-UT355S-xxxxxxxxxxxx",
+        b"Transmission 4.0.4",
+        Verdict::ClientMismatch(KnownClient::UTorrent, KnownClient::Transmission)
+    )]
+    #[test_case(
+        b"-LT2090-xxxxxxxxxxxx",
+        b"qBittorrent/4.6.2",
+        Verdict::ClientMismatch(KnownClient::LibtorrentRasterbar, KnownClient::QBittorrent)
+    )]
+    #[test_case(
+        b"-ZZ1234-xxxxxxxxxxxx",
+        b"qBittorrent/4.6.2",
+        Verdict::UnknownPeerId(ClientParsingError::UnknownAzureusStylePrefix(*b"ZZ"))
+    )]
+    #[test_case(
+        b"-qB4620-xxxxxxxxxxxx",
+        b"Not A Client 1.0",
+        Verdict::UnknownClientName
+    )]
+    fn test_cross_check(peer_id: &[u8; 20], v: &[u8], verdict: Verdict) {
+        assert_eq!(cross_check(PeerId::from(peer_id), v), verdict);
+    }
+
+    #[test_case(b"-qB4520-xxxxxxxxxxxx", b"qBittorrent/4.5.2", Verdict::Consistent)]
+    #[test_case(b"-TR4040-xxxxxxxxxxxx", b"Transmission/4.0.4", Verdict::Consistent)]
+    #[test_case(b"-DE211s-xxxxxxxxxxxx", b"Deluge 2.1.1", Verdict::Consistent)]
+    #[test_case(b"-lt0D80-xxxxxxxxxxxx", b"rtorrent/0.9.8/0.13.8", Verdict::Consistent)]
+    #[test_case(b"-lt0D80-xxxxxxxxxxxx", b"rtorrent/0.9.8", Verdict::Consistent)]
+    #[test_case(
+        b"-UT355S-xxxxxxxxxxxx",
+        b"uTorrent/3540(45000)",
+        Verdict::VersionMismatch(NumericVersion::new(&[3, 5, 5], None), NumericVersion::new(&[3, 5, 4], None))
+    )]
+    #[test_case(
+        b"-UT355S-xxxxxxxxxxxx",
+        b"qBittorrent/4.5.2",
+        Verdict::ClientMismatch(KnownClient::UTorrent, KnownClient::QBittorrent)
+    )]
+    fn test_cross_check_user_agent(peer_id: &[u8; 20], ua: &[u8], verdict: Verdict) {
+        assert_eq!(cross_check_user_agent(PeerId::from(peer_id), ua), verdict);
+    }
+
+    #[test_case("4.6.2", Some(NumericVersion::new(&[4, 6, 2], None)))]
+    #[test_case("2.94.1.3.7", Some(NumericVersion::new(&[2, 94, 1, 3], None)))]
+    #[test_case("1.8.2b", Some(NumericVersion::new(&[1, 8], None)))]
+    #[test_case("3.5.5.46200", Some(NumericVersion::new(&[3, 5, 5], None)))]
+    #[test_case("dev", None)]
+    fn test_numeric(version: &str, expected: Option<NumericVersion>) {
+        assert_eq!(numeric(version), expected);
+    }
+}
@@ -0,0 +1,96 @@
+// DHT nodes identify themselves in the `v` key of KRPC messages with a two-byte client code,
+// the same one Azureus-style peer IDs use, followed by a two-byte version
+
+use crate::client_styles::azureus::Azureus;
+use crate::client_styles::types::ClientStyle;
+use crate::errors::DhtVersionError;
+use crate::known_clients::KnownClient;
+use crate::version::NumericVersion;
+
+/// Client and version from the `v` key of a [BEP 5](https://www.bittorrent.org/beps/bep_0005.html)
+/// DHT message, returned by [`parse_dht_version`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DhtVersion {
+    /// The client, the same one [`parse_structured`](crate::parse_structured) returns for
+    /// peer IDs with the same Azureus-style tag.
+    pub client: KnownClient,
+    /// The two version bytes as a big-endian number. What they mean is up to the client,
+    /// see [`DhtVersion::numeric`].
+    pub version: u16,
+}
+
+impl DhtVersion {
+    /// The version as major and minor components, one per byte, which is how libtorrent
+    /// (Rasterbar) and Transmission fill them in. `None` for other clients, e.g. µTorrent
+    /// puts a build number there, use [`DhtVersion::version`] for those.
+    pub fn numeric(&self) -> Option<NumericVersion> {
+        matches!(
+            self.client,
+            KnownClient::LibtorrentRasterbar | KnownClient::Transmission
+        )
+        .then(|| NumericVersion::new(&self.version.to_be_bytes(), None))
+    }
+}
+
+/// Parses the 4-byte `v` value of a DHT message: a two-byte client code and a two-byte
+/// version. Client codes are looked up among the Azureus-style peer ID tags.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::{parse_dht_version, KnownClient};
+/// use tdyne_peer_id_registry::version::NumericVersion;
+///
+/// let parsed = parse_dht_version(b"LT\x01\x02").unwrap();
+/// assert_eq!(parsed.client, KnownClient::LibtorrentRasterbar);
+/// assert_eq!(parsed.version, 0x0102);
+/// assert_eq!(parsed.numeric(), NumericVersion::try_new(&[1, 2], None));
+/// ```
+pub fn parse_dht_version(v: impl AsRef<[u8]>) -> Result<DhtVersion, DhtVersionError> {
+    let v = v.as_ref();
+    let [b1, b2, v1, v2] = v
+        .try_into()
+        .map_err(|_| DhtVersionError::WrongLength(v.len()))?;
+
+    let client = Azureus::from_tag([b1, b2]).ok_or(DhtVersionError::UnknownClientCode([b1, b2]))?;
+
+    Ok(DhtVersion {
+        client: client.to_canonical(),
+        version: u16::from_be_bytes([v1, v2]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(b"LT\x01\x02", KnownClient::LibtorrentRasterbar, 0x0102)]
+    #[test_case(b"UT\xb3\x5e", KnownClient::UTorrent, 0xb35e)]
+    #[test_case(b"TR\x04\x00", KnownClient::Transmission, 0x0400)]
+    #[test_case(b"lt\x00\x0d", KnownClient::LibTorrentRakshasa, 0x000d)]
+    #[test_case(b"BI\x03\x05", KnownClient::BiglyBt, 0x0305)]
+    fn test_parse_dht_version(v: &[u8], client: KnownClient, version: u16) {
+        let parsed = parse_dht_version(v).unwrap();
+        assert_eq!(parsed.client, client);
+        assert_eq!(parsed.version, version);
+    }
+
+    #[test_case(b"LT\x01\x02", Some(NumericVersion::new(&[1, 2], None)))]
+    #[test_case(b"TR\x04\x00", Some(NumericVersion::new(&[4, 0], None)))]
+    #[test_case(b"UT\xb3\x5e", None)]
+    #[test_case(b"lt\x00\x0d", None)]
+    fn test_numeric(v: &[u8], numeric: Option<NumericVersion>) {
+        assert_eq!(parse_dht_version(v).unwrap().numeric(), numeric);
+    }
+
+    #[test_case(b"LT\x01", DhtVersionError::WrongLength(3))]
+    #[test_case(b"", DhtVersionError::WrongLength(0))]
+    #[test_case(b"ZZ\x01\x02", DhtVersionError::UnknownClientCode(*b"ZZ"))]
+    fn test_errors(v: &[u8], error: DhtVersionError) {
+        assert_eq!(parse_dht_version(v), Err(error));
+    }
+}
@@ -1,17 +1,18 @@
-use std::fmt;
+use core::fmt;
 
-///
+/// Errors that can arise while decoding the version of an already recognised client.
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VersionParsingError {
     /// Returned when the library expects the parsed client's version format to
     /// include a byte corresponding to an ASCII base 10 number, but the actual bytes
     /// is outside of `b'0'..=b'9'` range. Includes the offending byte.
-    DigitNotBase10(u8),
+    DigitNotBase10(#[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8),
     /// Same as DigitNotBase10, but for base 16.      
-    DigitNotBase16(u8),
+    DigitNotBase16(#[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8),
     /// Same as DigitNotBase10, but for base 62 (`0-9A-Za-z`).
-    DigitNotBase62(u8),
+    DigitNotBase62(#[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8),
     /// `tdyne_peer_id_registry` expects the versions to fit into `u8`. In some encodings
     /// it's possible to encode a number larger than `[u8::MAX]`; if this happens, the library
     /// returns this error.
@@ -19,11 +20,18 @@ pub enum VersionParsingError {
     /// Transmission version encoding includes one byte for a release type (release/beta/dev).
     /// If the actual peer ID has a byte that is not in the Transmission spec, the library
     /// returns this error. Includes the offending byte.
-    UnknownTransmissionReleaseType(u8),
+    UnknownTransmissionReleaseType(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8,
+    ),
     /// BitComet and BitLord have a prefix that is normally checked during client detection.
     /// If for some reason the header is not found during version parsing, this error is returned.
     /// Should never arise if the API is not misused.
-    UnexpectedBitCometBitLordHeader(u8, u8, u8, u8),
+    UnexpectedBitCometBitLordHeader(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8,
+    ),
 }
 
 impl fmt::Display for VersionParsingError {
@@ -61,33 +69,49 @@ impl fmt::Display for VersionParsingError {
     }
 }
 
-impl std::error::Error for VersionParsingError {}
+impl core::error::Error for VersionParsingError {}
 
-///
+/// Errors that can arise while recognising the client that generated a peer ID.
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ClientParsingError {
     /// The library detects the encoding style of a given peer ID first and then matches
     /// the encoded client identificator against the registry of all known identifiers.
     /// If the peer ID matches Azureus style, but the actual two-byte client identifier
     /// is unknown, the library returns this error.
-    UnknownAzureusStylePrefix([u8; 2]),
+    UnknownAzureusStylePrefix(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::bytes"))] [u8; 2],
+    ),
     /// Same but for Shad0w-style clients.
-    UnknownShadowStylePrefix(u8),
+    UnknownShadowStylePrefix(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8,
+    ),
     /// Same but for Mainline style clients.
-    UnknownMainlineClientPrefix([u8; 4]),
+    UnknownMainlineClientPrefix(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::bytes"))] [u8; 4],
+    ),
     /// Returned when the library fails to recognise the client.
     UnknownClient,
 }
 
-fn ascii_or_byte(b: u8) -> String {
-    if b.is_ascii_alphanumeric() {
-        char::from(b).to_string()
-    } else {
-        format!("\\x{:02}", b)
+// a Display wrapper rather than a String, so that displaying errors doesn't allocate
+struct AsciiOrByte(u8);
+
+impl fmt::Display for AsciiOrByte {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_ascii_alphanumeric() {
+            write!(f, "{}", char::from(self.0))
+        } else {
+            write!(f, "\\x{:02}", self.0)
+        }
     }
 }
 
+fn ascii_or_byte(b: u8) -> AsciiOrByte {
+    AsciiOrByte(b)
+}
+
 impl fmt::Display for ClientParsingError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -123,4 +147,342 @@ impl fmt::Display for ClientParsingError {
     }
 }
 
-impl std::error::Error for ClientParsingError {}
+impl core::error::Error for ClientParsingError {}
+
+/// Errors that can arise while encoding a peer ID with [`encode`](crate::encode).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EncodingError {
+    /// The client is known, but the library can't generate its peer IDs. Either the client
+    /// doesn't follow any of the structured styles (e.g. it's recognised by a substring), or
+    /// the library doesn't know how the client encodes its version.
+    UnsupportedClient,
+    /// The version doesn't belong to the style the client uses, for example a Shadow-style
+    /// version passed for Transmission, or no version passed for an Azureus-style client.
+    MismatchedVersionStyle,
+    /// One of the version components doesn't fit into the digits the client's style provides.
+    /// Includes the offending component.
+    ComponentOutOfRange(u8),
+    /// The version is of the right style, but can't be represented in it. For example,
+    /// Transmission switched to a three-component version in 3.0, so
+    /// `Transmission::Current(2, 94, 0, None)` doesn't have an encoding.
+    UnrepresentableVersion,
+    /// The random bytes supplied to the encoder would make the resulting peer ID parse as
+    /// a different client (for example, ending the peer ID in `UDP0` triggers BitSpirit
+    /// detection). Retry with different random bytes.
+    ConflictingRandomBytes,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedClient => {
+                write!(f, "Can't encode a peer ID for this client")
+            }
+            Self::MismatchedVersionStyle => {
+                write!(f, "Version doesn't match the client's encoding style")
+            }
+            Self::ComponentOutOfRange(x) => {
+                write!(f, "Version component {x} doesn't fit the client's encoding")
+            }
+            Self::UnrepresentableVersion => {
+                write!(f, "Version can't be represented in the client's encoding")
+            }
+            Self::ConflictingRandomBytes => {
+                write!(
+                    f,
+                    "Random bytes make the peer ID look like a different client"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for EncodingError {}
+
+/// Returned when parsing a [`KnownClient`](crate::KnownClient) from a string that isn't
+/// one of the identifiers returned by [`KnownClient::id`](crate::KnownClient::id).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnknownClientId;
+
+impl fmt::Display for UnknownClientId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown client identifier")
+    }
+}
+
+impl core::error::Error for UnknownClientId {}
+
+/// Returned by [`parse_client_name`](crate::parse_client_name) when the name doesn't belong
+/// to any known client.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnknownClientName;
+
+impl fmt::Display for UnknownClientName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown client name")
+    }
+}
+
+impl core::error::Error for UnknownClientName {}
+
+/// Returned when parsing an [`AzureusVersionStyle`](crate::version::AzureusVersionStyle)
+/// from a string that isn't one of its variant names.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnknownVersionStyle;
+
+impl fmt::Display for UnknownVersionStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown version style")
+    }
+}
+
+impl core::error::Error for UnknownVersionStyle {}
+
+/// Errors that can arise while registering clients with
+/// [`RegistryBuilder`](crate::RegistryBuilder).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RegistryError {
+    /// The same Azureus-style tag was registered twice. Includes the tag.
+    DuplicateAzureusTag(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::bytes"))] [u8; 2],
+    ),
+    /// The same Shad0w-style tag was registered twice. Includes the tag.
+    DuplicateShadowTag(#[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8),
+    /// Shad0w-style tags have to be printable ASCII characters other than `-`.
+    /// Includes the offending tag.
+    InvalidShadowTag(#[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::byte"))] u8),
+    /// The substring is empty or doesn't fit into a peer ID at the given offset.
+    /// Includes the offset and the length of the substring.
+    SubstringOutOfBounds(usize, usize),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateAzureusTag([b1, b2]) => {
+                write!(
+                    f,
+                    "Azureus-style tag \"{}{}\" is registered twice",
+                    ascii_or_byte(*b1),
+                    ascii_or_byte(*b2)
+                )
+            }
+            Self::DuplicateShadowTag(b) => {
+                write!(
+                    f,
+                    "Shadow-style tag \"{}\" is registered twice",
+                    ascii_or_byte(*b)
+                )
+            }
+            Self::InvalidShadowTag(b) => {
+                write!(
+                    f,
+                    "\"{}\" can't be used as a Shadow-style tag",
+                    ascii_or_byte(*b)
+                )
+            }
+            Self::SubstringOutOfBounds(offset, len) => {
+                write!(
+                    f,
+                    "A substring of length {len} at offset {offset} doesn't fit into a peer ID"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for RegistryError {}
+
+/// Errors that can arise while extracting a peer ID from an announce query string with
+/// [`peer_id_from_announce`](crate::peer_id_from_announce) and friends.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnnounceError {
+    /// The query string doesn't have a `peer_id` parameter.
+    MissingPeerId,
+    /// A `%` isn't followed by two hex digits.
+    InvalidPercentEncoding,
+    /// The decoded peer ID isn't 20 bytes long. Includes the actual length.
+    WrongLength(usize),
+    /// The peer ID was decoded, but the client wasn't recognised.
+    ClientParsing(ClientParsingError),
+}
+
+impl From<ClientParsingError> for AnnounceError {
+    fn from(e: ClientParsingError) -> Self {
+        Self::ClientParsing(e)
+    }
+}
+
+impl fmt::Display for AnnounceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingPeerId => {
+                write!(f, "The announce doesn't have a peer_id parameter")
+            }
+            Self::InvalidPercentEncoding => {
+                write!(f, "peer_id isn't correctly percent-encoded")
+            }
+            Self::WrongLength(len) => {
+                write!(f, "peer_id is {len} bytes long instead of 20")
+            }
+            Self::ClientParsing(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for AnnounceError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::ClientParsing(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Errors that can arise while parsing the `v` value of a DHT message with
+/// [`parse_dht_version`](crate::parse_dht_version).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DhtVersionError {
+    /// The value isn't 4 bytes long. Includes the actual length.
+    WrongLength(usize),
+    /// The two-byte client code isn't a known Azureus-style tag. Includes the code.
+    UnknownClientCode(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::bytes"))] [u8; 2],
+    ),
+}
+
+impl fmt::Display for DhtVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::WrongLength(len) => {
+                write!(f, "DHT version is {len} bytes long instead of 4")
+            }
+            Self::UnknownClientCode([b1, b2]) => {
+                write!(
+                    f,
+                    "\"{}{}\" doesn't match a known DHT client code",
+                    ascii_or_byte(*b1),
+                    ascii_or_byte(*b2)
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for DhtVersionError {}
+
+/// Errors that can arise while parsing a handshake with
+/// [`parse_handshake`](crate::parse_handshake).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HandshakeError {
+    /// The handshake isn't 68 bytes long. Includes the actual length.
+    WrongLength(usize),
+    /// The handshake doesn't start with `\x13BitTorrent protocol`.
+    WrongProtocol,
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::WrongLength(len) => {
+                write!(f, "Handshake is {len} bytes long instead of 68")
+            }
+            Self::WrongProtocol => {
+                write!(
+                    f,
+                    "Handshake doesn't start with the BitTorrent protocol string"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for HandshakeError {}
+
+/// Location of an entry in a registry overlay, e.g. `azureus[2]` for the third
+/// `[[azureus]]` entry. Part of [`OverlayError`].
+#[cfg(feature = "overlay")]
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct OverlayEntry {
+    /// The section, `"azureus"`, `"shadow"` or `"substring"`.
+    pub section: &'static str,
+    /// Zero-based position of the entry within its section.
+    pub index: usize,
+}
+
+#[cfg(feature = "overlay")]
+impl fmt::Display for OverlayEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}]", self.section, self.index)
+    }
+}
+
+/// Errors that can arise while loading a registry overlay with
+/// [`RegistryBuilder::overlay_file`](crate::RegistryBuilder::overlay_file) and friends.
+#[cfg(feature = "overlay")]
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum OverlayError {
+    /// The overlay file couldn't be read. Includes the kind of the I/O error.
+    Io(std::io::ErrorKind),
+    /// The overlay file extension is neither `.toml` nor `.json`.
+    UnsupportedFormat,
+    /// The overlay isn't valid TOML or JSON, or doesn't follow the overlay schema.
+    /// Includes the parser's message, which points at the offending line.
+    Syntax(String),
+    /// The tag has the wrong length: Azureus-style tags are two bytes long and Shad0w-style
+    /// tags are one byte long. Includes the entry and the tag.
+    InvalidTag(OverlayEntry, String),
+    /// The version style isn't one of the
+    /// [`AzureusVersionStyle`](crate::version::AzureusVersionStyle) variants. Includes the
+    /// entry and the style.
+    UnknownVersionStyle(OverlayEntry, String),
+    /// The entry couldn't be registered. Includes the entry and the reason.
+    Registry(OverlayEntry, RegistryError),
+}
+
+#[cfg(feature = "overlay")]
+impl fmt::Display for OverlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(kind) => {
+                write!(f, "Can't read the overlay: {kind}")
+            }
+            Self::UnsupportedFormat => {
+                write!(f, "Overlays have to be .toml or .json files")
+            }
+            Self::Syntax(message) => {
+                write!(f, "Can't parse the overlay: {message}")
+            }
+            Self::InvalidTag(entry, tag) => {
+                write!(f, "{entry}: \"{tag}\" has the wrong length for a tag")
+            }
+            Self::UnknownVersionStyle(entry, style) => {
+                write!(f, "{entry}: \"{style}\" is not a known version style")
+            }
+            Self::Registry(entry, e) => {
+                write!(f, "{entry}: {e}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "overlay")]
+impl core::error::Error for OverlayError {}
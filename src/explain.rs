@@ -0,0 +1,182 @@
+// a trace of the decisions `parse_structured()` makes, for when a peer ID resolves
+// into something unexpected
+
+use crate::client::{Client, Step};
+use crate::errors::ClientParsingError;
+use crate::ParsedStructured;
+use std::fmt;
+use std::ops::Range;
+use tdyne_peer_id::PeerId;
+
+/// The result of [`parse_explained`]: the parse result together with the checks that led
+/// to it. `Display` prints a human-readable trace.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Explanation {
+    /// The explained peer ID.
+    pub peer_id: [u8; 20],
+    /// The checks made, in order. The last one is the one that decided the style.
+    pub steps: Vec<Step>,
+    /// Bytes of the peer ID the version was decoded from, `None` if the version wasn't
+    /// decoded from the peer ID (e.g. substring matches, clients without a version) or
+    /// if the peer ID wasn't recognised.
+    pub version_bytes: Option<Range<usize>>,
+    /// Same as what [`parse_structured`](crate::parse_structured) returns.
+    pub result: Result<ParsedStructured, ClientParsingError>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "peer ID: {}", self.peer_id.escape_ascii())?;
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(f, "{}. {step}", i + 1)?;
+        }
+
+        let parsed = match &self.result {
+            Ok(parsed) => parsed,
+            Err(e) => return write!(f, "error: {e}"),
+        };
+        writeln!(f, "client: {} [{}]", parsed.client, parsed.style)?;
+        if let Some(range) = &self.version_bytes {
+            writeln!(
+                f,
+                "version bytes {range:?}: `{}`",
+                self.peer_id[range.clone()].escape_ascii()
+            )?;
+        }
+        match &parsed.version {
+            Ok(Some(version)) => write!(f, "version: {version}"),
+            Ok(None) => write!(f, "version: none"),
+            Err(e) => write!(f, "version error: {e}"),
+        }
+    }
+}
+
+/// Same as [`parse_structured`](crate::parse_structured), but also records the checks the
+/// parser made and the bytes it decoded the version from.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::parse_explained;
+///
+/// let explanation = parse_explained(PeerId::from(b"-TR4040-xxxxxxxxxxxx"));
+/// assert_eq!(
+///     explanation.to_string(),
+///     "peer ID: -TR4040-xxxxxxxxxxxx\n\
+///      1. spoof mode marker `UDP0`/`HTTPBT`: no\n\
+///      2. Azureus style `-XX1234-`: yes\n\
+///      client: Transmission [Azureus]\n\
+///      version bytes 3..7: `4040`\n\
+///      version: 4.0.4"
+/// );
+/// ```
+pub fn parse_explained(peer_id: PeerId) -> Explanation {
+    let mut steps = Vec::new();
+    let client = Client::try_from_traced(peer_id.0, |step| steps.push(step));
+
+    Explanation {
+        peer_id: peer_id.0,
+        steps,
+        version_bytes: client
+            .as_ref()
+            .ok()
+            .and_then(|c| c.version_bytes(peer_id.0)),
+        result: client.map(|client| ParsedStructured {
+            client: client.to_canonical(),
+            style: client.style(),
+            version: client.parse_version(peer_id.0),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_structured;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+    use test_case::test_case;
+
+    #[test_case(
+        b"-TR4040-xxxxxxxxUDP0",
+        &[Step::SpoofMarker(true), Step::BitSpirit(false), Step::BitComet(false)],
+        None
+    )]
+    #[test_case(
+        b"exbc0059xxxxxxxxxxxx",
+        &[
+            Step::SpoofMarker(false),
+            Step::Azureus(false),
+            Step::Shadow(false),
+            Step::Mainline(false),
+            Step::BitSpirit(false),
+            Step::BitComet(true),
+        ],
+        Some(4..7)
+    )]
+    #[test_case(
+        b"T03I--008gY6iB6Aq27C",
+        &[Step::SpoofMarker(false), Step::Azureus(false), Step::Shadow(true)],
+        Some(1..5)
+    )]
+    #[test_case(
+        b"M4-20-8-xxxxxxxxxxxx",
+        &[
+            Step::SpoofMarker(false),
+            Step::Azureus(false),
+            Step::Shadow(false),
+            Step::Mainline(true),
+        ],
+        Some(1..7)
+    )]
+    #[test_case(
+        b"-UT170-xxxxxxxxxxxxx",
+        &[
+            Step::SpoofMarker(false),
+            Step::Azureus(false),
+            Step::Shadow(false),
+            Step::Mainline(false),
+            Step::BitSpirit(false),
+            Step::BitComet(false),
+            Step::Substring(Some((b"-UT170-", 0))),
+        ],
+        None
+    )]
+    #[test_case(
+        b"-ZZ1234-xxxxxxxxxxxx",
+        &[Step::SpoofMarker(false), Step::Azureus(true)],
+        None
+    )]
+    fn test_steps(peer_id: &[u8; 20], steps: &[Step], version_bytes: Option<Range<usize>>) {
+        let explanation = parse_explained(PeerId::from(peer_id));
+        assert_eq!(explanation.steps, steps);
+        assert_eq!(explanation.version_bytes, version_bytes);
+    }
+
+    #[test]
+    fn test_display() {
+        let explanation = parse_explained(PeerId::from(b"xxxxxxxxxxxxxxxxxxx\x00"));
+        assert_eq!(
+            explanation.to_string(),
+            "peer ID: xxxxxxxxxxxxxxxxxxx\\x00\n\
+             1. spoof mode marker `UDP0`/`HTTPBT`: no\n\
+             2. Azureus style `-XX1234-`: no\n\
+             3. Shad0w style `X1234-`: no\n\
+             4. Mainline style `M1-2-3--`: no\n\
+             5. BitSpirit `BS` at 2..4: no\n\
+             6. BitComet header `exbc`/`FUTB`/`xUTB`: no\n\
+             7. substring table: no match\n\
+             error: Unknown client"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_same_as_parse_structured(bytes in prop::array::uniform20(any::<u8>())) {
+            let peer_id = PeerId::from(&bytes);
+            prop_assert_eq!(parse_explained(peer_id).result, parse_structured(peer_id));
+        }
+    }
+}
@@ -0,0 +1,382 @@
+// the BEP 3 handshake carries the peer ID next to 8 reserved bytes, whose bits announce
+// the extensions the client supports. Clients set them in recognisable patterns, so the bits
+// confirm or contradict the client in the peer ID
+
+use crate::client_info::Family;
+use crate::errors::{ClientParsingError, HandshakeError};
+use crate::known_clients::KnownClient;
+use crate::parse_structured;
+use crate::version::NumericVersion;
+use crate::ParsedStructured;
+use std::fmt;
+use tdyne_peer_id::PeerId;
+
+const PROTOCOL: &[u8; 20] = b"\x13BitTorrent protocol";
+const HANDSHAKE_LEN: usize = 68;
+
+// µTorrent introduced the extension protocol in 1.7
+const UTORRENT_EXTENSION_PROTOCOL: NumericVersion = NumericVersion::new(&[1, 7], None);
+// and Azureus introduced its messaging protocol in 2.3
+const AZUREUS_MESSAGING: NumericVersion = NumericVersion::new(&[2, 3], None);
+
+/// An extension announced in the reserved bytes of a handshake, see
+/// [BEP 4](https://www.bittorrent.org/beps/bep_0004.html).
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Extension {
+    /// [BEP 10](https://www.bittorrent.org/beps/bep_0010.html) extension protocol, bit
+    /// `0x10` of byte 5.
+    ExtensionProtocol,
+    /// [BEP 5](https://www.bittorrent.org/beps/bep_0005.html) DHT, bit `0x01` of byte 7.
+    Dht,
+    /// [BEP 6](https://www.bittorrent.org/beps/bep_0006.html) Fast extension, bit `0x04`
+    /// of byte 7.
+    Fast,
+    /// Azureus Messaging Protocol, bit `0x80` of byte 0.
+    AzureusMessaging,
+    /// BitComet extension protocol, `ex` in bytes 0 and 1.
+    BitComet,
+    /// [BEP 52](https://www.bittorrent.org/beps/bep_0052.html) upgrade to BitTorrent v2,
+    /// bit `0x10` of byte 7.
+    V2Upgrade,
+}
+
+impl Extension {
+    /// All extensions, in the order [`Handshake::extensions`] returns them.
+    pub const ALL: [Self; 6] = [
+        Self::ExtensionProtocol,
+        Self::Dht,
+        Self::Fast,
+        Self::AzureusMessaging,
+        Self::BitComet,
+        Self::V2Upgrade,
+    ];
+
+    /// Whether the reserved bytes of a handshake announce the extension.
+    ///
+    /// ```
+    /// use tdyne_peer_id_registry::Extension;
+    ///
+    /// let reserved = [0, 0, 0, 0, 0, 0x10, 0, 0x05];
+    /// assert!(Extension::ExtensionProtocol.is_set(reserved));
+    /// assert!(!Extension::AzureusMessaging.is_set(reserved));
+    /// ```
+    pub fn is_set(self, reserved: [u8; 8]) -> bool {
+        match self {
+            Self::ExtensionProtocol => reserved[5] & 0x10 != 0,
+            Self::Dht => reserved[7] & 0x01 != 0,
+            Self::Fast => reserved[7] & 0x04 != 0,
+            Self::AzureusMessaging => reserved[0] & 0x80 != 0,
+            Self::BitComet => reserved[..2] == *b"ex",
+            Self::V2Upgrade => reserved[7] & 0x10 != 0,
+        }
+    }
+}
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let repr = match self {
+            Self::ExtensionProtocol => "extension protocol",
+            Self::Dht => "DHT",
+            Self::Fast => "Fast extension",
+            Self::AzureusMessaging => "Azureus Messaging Protocol",
+            Self::BitComet => "BitComet extension protocol",
+            Self::V2Upgrade => "v2 upgrade",
+        };
+        write!(f, "{repr}")
+    }
+}
+
+/// A conflict between the reserved bytes of a handshake and the client in its peer ID,
+/// returned in [`Handshake::inconsistencies`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Inconsistency {
+    /// The client always announces the extension, but the handshake doesn't.
+    MissingBit(Extension),
+    /// The handshake announces an extension the client doesn't implement, e.g. the BitComet
+    /// bits sent by a non-BitComet peer ID.
+    UnexpectedBit(Extension),
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingBit(e) => {
+                write!(f, "Doesn't announce {e}, which the client always does")
+            }
+            Self::UnexpectedBit(e) => {
+                write!(f, "Announces {e}, which the client doesn't implement")
+            }
+        }
+    }
+}
+
+/// A parsed BEP 3 handshake, returned by [`parse_handshake`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Handshake {
+    /// The reserved bytes, see [`Handshake::extensions`].
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::bytes"))]
+    pub reserved: [u8; 8],
+    /// The info-hash of the torrent. For BitTorrent v2 torrents it's the truncated
+    /// SHA-256 hash.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::bytes"))]
+    pub info_hash: [u8; 20],
+    /// The peer ID.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::bytes"))]
+    pub peer_id: [u8; 20],
+    /// Same as what [`parse_structured`](crate::parse_structured) returns for the peer ID.
+    pub client: Result<ParsedStructured, ClientParsingError>,
+    /// Conflicts between the reserved bytes and the client. Empty if the peer ID wasn't
+    /// recognised.
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl Handshake {
+    /// Extensions announced in the reserved bytes, in the order of [`Extension::ALL`].
+    pub fn extensions(&self) -> impl Iterator<Item = Extension> + '_ {
+        Extension::ALL
+            .into_iter()
+            .filter(|e| e.is_set(self.reserved))
+    }
+}
+
+/// Parses the 68-byte handshake that opens a BitTorrent connection: the protocol string,
+/// the reserved bytes, the info-hash and the peer ID.
+///
+/// The reserved bytes are checked against the bits the peer ID's client is known to set:
+/// µTorrent (since 1.7) and the libtorrent (Rasterbar) family always announce the extension
+/// protocol, Azureus and its successors their messaging protocol, and BitComet and BitLord
+/// their own extension protocol. The Azureus and the BitComet bits are only set by those
+/// clients, and µTorrent doesn't implement BitTorrent v2. The DHT bit follows the user's
+/// settings, so it's never reported.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::{parse_handshake, Extension, Inconsistency, KnownClient};
+///
+/// let mut bytes = Vec::from(*b"\x13BitTorrent protocol");
+/// bytes.extend([0, 0, 0, 0, 0, 0, 0, 0x05]);
+/// bytes.extend([0xaa; 20]);
+/// bytes.extend(b"-UT355S-xxxxxxxxxxxx");
+///
+/// let handshake = parse_handshake(&bytes).unwrap();
+/// assert_eq!(handshake.client.as_ref().unwrap().client, KnownClient::UTorrent);
+/// assert_eq!(
+///     handshake.extensions().collect::<Vec<_>>(),
+///     [Extension::Dht, Extension::Fast]
+/// );
+/// assert_eq!(
+///     handshake.inconsistencies,
+///     [Inconsistency::MissingBit(Extension::ExtensionProtocol)]
+/// );
+/// ```
+pub fn parse_handshake(handshake: impl AsRef<[u8]>) -> Result<Handshake, HandshakeError> {
+    let handshake = handshake.as_ref();
+    if handshake.len() != HANDSHAKE_LEN {
+        return Err(HandshakeError::WrongLength(handshake.len()));
+    }
+    if !handshake.starts_with(PROTOCOL) {
+        return Err(HandshakeError::WrongProtocol);
+    }
+
+    let reserved: [u8; 8] = handshake[20..28].try_into().expect("8 bytes");
+    let info_hash: [u8; 20] = handshake[28..48].try_into().expect("20 bytes");
+    let peer_id: [u8; 20] = handshake[48..].try_into().expect("20 bytes");
+
+    let client = parse_structured(PeerId::from(&peer_id));
+    let inconsistencies = match &client {
+        Ok(parsed) => inconsistencies(parsed, reserved),
+        Err(_) => Vec::new(),
+    };
+
+    Ok(Handshake {
+        reserved,
+        info_hash,
+        peer_id,
+        client,
+        inconsistencies,
+    })
+}
+
+fn inconsistencies(parsed: &ParsedStructured, reserved: [u8; 8]) -> Vec<Inconsistency> {
+    let missing = required(parsed)
+        .iter()
+        .filter(|e| !e.is_set(reserved))
+        .map(|e| Inconsistency::MissingBit(*e));
+    let unexpected = Extension::ALL
+        .into_iter()
+        .filter(|e| e.is_set(reserved) && unexpected(parsed.client, *e))
+        .map(Inconsistency::UnexpectedBit);
+    missing.chain(unexpected).collect()
+}
+
+// bits the client sets regardless of its settings
+fn required(parsed: &ParsedStructured) -> &'static [Extension] {
+    let older_than = |version| matches!(&parsed.version, Ok(Some(v)) if v.numeric().is_some_and(|v| v < version));
+
+    match parsed.client.family() {
+        _ if is_bitcomet(parsed.client) => &[Extension::BitComet],
+        Some(Family::UTorrent)
+            if !(parsed.client == KnownClient::UTorrent
+                && older_than(UTORRENT_EXTENSION_PROTOCOL)) =>
+        {
+            &[Extension::ExtensionProtocol]
+        }
+        Some(Family::Libtorrent) => &[Extension::ExtensionProtocol],
+        Some(Family::Azureus)
+            if !(parsed.client == KnownClient::Vuze && older_than(AZUREUS_MESSAGING)) =>
+        {
+            &[Extension::AzureusMessaging]
+        }
+        _ => &[],
+    }
+}
+
+// bits only other clients set
+fn unexpected(client: KnownClient, extension: Extension) -> bool {
+    match extension {
+        Extension::AzureusMessaging => client.family() != Some(Family::Azureus),
+        Extension::BitComet => !is_bitcomet(client),
+        Extension::V2Upgrade => client.family() == Some(Family::UTorrent),
+        _ => false,
+    }
+}
+
+fn is_bitcomet(client: KnownClient) -> bool {
+    matches!(client, KnownClient::BitComet | KnownClient::BitLord)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    const UTORRENT: [u8; 8] = [0, 0, 0, 0, 0, 0x10, 0, 0x05];
+    const VUZE: [u8; 8] = [0x80, 0, 0, 0, 0, 0x10, 0, 0x01];
+    const BITCOMET: [u8; 8] = [b'e', b'x', 0, 0, 0, 0x10, 0, 0x05];
+    const NONE: [u8; 8] = [0; 8];
+
+    fn handshake(reserved: [u8; 8], peer_id: &[u8; 20]) -> Vec<u8> {
+        let mut bytes = Vec::from(*PROTOCOL);
+        bytes.extend(reserved);
+        bytes.extend([0xaa; 20]);
+        bytes.extend(peer_id);
+        bytes
+    }
+
+    #[test_case(UTORRENT, b"-UT355S-xxxxxxxxxxxx", &[])]
+    #[test_case(
+        NONE,
+        b"-UT355S-xxxxxxxxxxxx",
+        &[Inconsistency::MissingBit(Extension::ExtensionProtocol)]
+    )]
+    #[test_case(NONE, b"-UT1610-xxxxxxxxxxxx", &[])]
+    #[test_case(
+        [0, 0, 0, 0, 0, 0x10, 0, 0x15],
+        b"-UT355S-xxxxxxxxxxxx",
+        &[Inconsistency::UnexpectedBit(Extension::V2Upgrade)]
+    )]
+    #[test_case([0, 0, 0, 0, 0, 0x10, 0, 0x15], b"-qB4620-xxxxxxxxxxxx", &[])]
+    #[test_case(
+        NONE,
+        b"-qB4620-xxxxxxxxxxxx",
+        &[Inconsistency::MissingBit(Extension::ExtensionProtocol)]
+    )]
+    #[test_case(VUZE, b"-AZ5750-xxxxxxxxxxxx", &[])]
+    #[test_case(
+        UTORRENT,
+        b"-AZ5750-xxxxxxxxxxxx",
+        &[Inconsistency::MissingBit(Extension::AzureusMessaging)]
+    )]
+    #[test_case(NONE, b"-AZ2206-xxxxxxxxxxxx", &[])]
+    #[test_case(
+        VUZE,
+        b"-TR4040-xxxxxxxxxxxx",
+        &[Inconsistency::UnexpectedBit(Extension::AzureusMessaging)]
+    )]
+    #[test_case(BITCOMET, b"exbc0059xxxxxxxxxxxx", &[])]
+    #[test_case(
+        UTORRENT,
+        b"exbc0059xxxxxxxxxxxx",
+        &[Inconsistency::MissingBit(Extension::BitComet)]
+    )]
+    // BitComet's spoof mode sends other clients' peer IDs, but keeps its own bits
+    #[test_case(
+        BITCOMET,
+        b"-UT355S-xxxxxxxxUDP0",
+        &[Inconsistency::UnexpectedBit(Extension::BitComet)]
+    )]
+    #[test_case(
+        BITCOMET,
+        b"-qB4620-xxxxxxxxxxxx",
+        &[Inconsistency::UnexpectedBit(Extension::BitComet)]
+    )]
+    #[test_case(NONE, b"-TR4040-xxxxxxxxxxxx", &[])]
+    #[test_case(VUZE, b"-ZZ1234-xxxxxxxxxxxx", &[])]
+    fn test_inconsistencies(reserved: [u8; 8], peer_id: &[u8; 20], expected: &[Inconsistency]) {
+        let parsed = parse_handshake(handshake(reserved, peer_id)).unwrap();
+        assert_eq!(parsed.inconsistencies, expected);
+    }
+
+    #[test_case(NONE, &[])]
+    #[test_case(UTORRENT, &[Extension::ExtensionProtocol, Extension::Dht, Extension::Fast])]
+    #[test_case(VUZE, &[Extension::ExtensionProtocol, Extension::Dht, Extension::AzureusMessaging])]
+    #[test_case(
+        BITCOMET,
+        &[Extension::ExtensionProtocol, Extension::Dht, Extension::Fast, Extension::BitComet]
+    )]
+    #[test_case(
+        [0xff; 8],
+        &[
+            Extension::ExtensionProtocol,
+            Extension::Dht,
+            Extension::Fast,
+            Extension::AzureusMessaging,
+            Extension::V2Upgrade,
+        ]
+    )]
+    fn test_extensions(reserved: [u8; 8], expected: &[Extension]) {
+        let parsed = parse_handshake(handshake(reserved, b"-TR4040-xxxxxxxxxxxx")).unwrap();
+        assert_eq!(parsed.extensions().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_fields() {
+        let parsed = parse_handshake(handshake(UTORRENT, b"-TR4040-xxxxxxxxxxxx")).unwrap();
+        assert_eq!(parsed.reserved, UTORRENT);
+        assert_eq!(parsed.info_hash, [0xaa; 20]);
+        assert_eq!(&parsed.peer_id, b"-TR4040-xxxxxxxxxxxx");
+        assert_eq!(
+            parsed.client.map(|c| c.client),
+            Ok(KnownClient::Transmission)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut bytes = handshake(NONE, b"-TR4040-xxxxxxxxxxxx");
+        assert_eq!(
+            parse_handshake(&bytes[..67]),
+            Err(HandshakeError::WrongLength(67))
+        );
+        assert_eq!(parse_handshake(b""), Err(HandshakeError::WrongLength(0)));
+
+        bytes[1] = b'b';
+        assert_eq!(parse_handshake(&bytes), Err(HandshakeError::WrongProtocol));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Inconsistency::MissingBit(Extension::ExtensionProtocol).to_string(),
+            "Doesn't announce extension protocol, which the client always does"
+        );
+    }
+}
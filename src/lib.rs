@@ -1,22 +1,21 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_docs)]
 
-//! # BitTorrent peer ID registry/parser/(soon) encoder
+//! # BitTorrent peer ID registry/parser/encoder
 //!
 //! By convention, BitTorrent clients identify themselves and their versions in
 //! peer IDs they send to trackers and other clients.
 //! Unfortunately, there is no single client/version encoding, so over time different clients
 //! adopted different conventions, which made parsing peer IDs difficult.
-//! This crate provides a comprehensive peer ID parser and a registry of all known
-//! BitTorrent clients.
-//!
-//! Peer ID encoding is one of immediate goals, see "Roadmap"
-//! below for details.
+//! This crate provides a comprehensive peer ID parser, a registry of all known
+//! BitTorrent clients and an encoder that only generates peer IDs in their registered formats.
 //!
 //! This crate uses [`tdyne_peer_id`] to encode peer IDs.
 //!
 //! Example:
 //!
 //! ```
+//! # #[cfg(feature = "std")] {
 //! use tdyne_peer_id::PeerId;
 //! use tdyne_peer_id_registry::parse;
 //!
@@ -29,8 +28,79 @@
 //!     .expect("valid version encoding")
 //!     .expect("Transmission does encode a version in its peer ID");
 //! assert_eq!(version, "4.0.4 (Dev)");
+//! # }
 //! ```
 //!
+//! Projects that need to act on the parsed information can use [`parse_structured`]
+//! instead, which returns an allocation-free tree of enums and structs:
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! use tdyne_peer_id::PeerId;
+//! use tdyne_peer_id_registry::{parse_structured, KnownClient, Style};
+//! use tdyne_peer_id_registry::version::{AzureusVersion, Transmission, Version};
+//!
+//! let parsed = parse_structured(PeerId::from(b"-TR4040-xxxxxxxxxxxx")).unwrap();
+//! assert_eq!(parsed.client, KnownClient::Transmission);
+//! assert_eq!(parsed.style, Style::Azureus);
+//!
+//! match parsed.version {
+//!     Ok(Some(Version::Azureus(AzureusVersion::Transmission(Transmission::Current(
+//!         major,
+//!         ..
+//!     ))))) => assert_eq!(major, 4),
+//!     _ => unreachable!(),
+//! }
+//! # }
+//! ```
+//!
+//! Encoding goes the other way, from a known client and a structured version:
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! use tdyne_peer_id_registry::{encode, KnownClient};
+//! use tdyne_peer_id_registry::version::{ThreeBase16AlphaBeta, AlphaBetaSuffix};
+//!
+//! let peer_id = encode(
+//!     KnownClient::UTorrent,
+//!     Some(ThreeBase16AlphaBeta(3, 5, 5, Some(AlphaBetaSuffix::Beta)).into()),
+//!     *b"xxxxxxxxxxxxxxxxxxxx",
+//! )
+//! .expect("known client and a matching version style");
+//! assert_eq!(&peer_id.0, b"-UT355B-xxxxxxxxxxxx");
+//! # }
+//! ```
+//!
+//! ## Cargo features
+//!
+//! * `std` (default): [`parse`], [`Registry`], the aggregating types and the parsers of
+//!   announces, client names and handshakes, and the [`PeerId`]-based entry points.
+//!   Without it, the crate is `no_std`, see below
+//! * `serde`: `Serialize` and `Deserialize` for parse results, errors and
+//!   [`StatsSnapshot`]. Raw bytes in errors are written both as escaped ASCII and as hex.
+//!   Structured versions are `Serialize` only
+//! * `overlay`: load runtime [`Registry`] additions from TOML or JSON files
+//! * `cli`: the `tdyne-peer-id` command-line inspector. It takes peer IDs as raw text, hex,
+//!   percent-encoded or base64 strings, from the arguments or from stdin one per line, and
+//!   prints the client, the version and the encoding style as text, JSON lines or CSV:
+//!
+//!   ```sh
+//!   $ tdyne-peer-id %2DTR4040%2Dxxxxxxxxxxxx
+//!   %2DTR4040%2Dxxxxxxxxxxxx: Transmission 4.0.4 [Azureus]
+//!   $ zcat announces.log.gz | cut -f3 | tdyne-peer-id --format csv > clients.csv
+//!   ```
+//!
+//! ## Allocations and `no_std`
+//!
+//! [`parse_structured`], [`encode`] and the `Display` implementations of the structured
+//! types and errors don't allocate, which is checked by the test suite. [`parse`] and the
+//! aggregating types ([`Registry`], [`CachedParser`], [`SwarmStats`]) do.
+//!
+//! Everything that allocates is behind the default `std` feature, and so is
+//! [`tdyne_peer_id`], which depends on `std`. Without the feature the crate is `no_std`,
+//! and [`parse_structured_bytes`] and [`encode_bytes`] take and return raw 20-byte arrays
+//! instead of [`PeerId`]s. CI builds it for `thumbv7em-none-eabihf` to keep it that way.
+//!
 //! ## Current status
 //!
 //! * used in production on [TORRENTDYNE](https://torrentdyne.com)
@@ -42,42 +112,89 @@
 //!
 //! ## Roadmap
 //!
-//! ### Encoding peer IDs
-//!
-//! A peer ID formatting API that only accepts known clients (in release mode) and
-//! takes choices out of peer ID formatting would help the ecosystem to stay more consistent.
-//!
 //! ### Test parity with Transmission
 //!
 //! Transmission has
 //! [an extensive peer ID parser](https://github.com/transmission/transmission/blob/0c52b710ad241c2b68cb9c7a9eb68a8532b290d0/libtransmission/clients.cc).
 //! Right now the Venn diagram of clients that `tdyne_peer_id_registry` and Transmission
 //! can handle is two intersecting circles. It needs to get closer to two concentric rings.
-//!
-//! ### Structured allocation-free API
-//!
-//! `tdyne_peer_id_registry` is designed to parse into an allocation-free tree
-//! of structs and enums. In the current version the tree is not exposed as it is not stable yet
-//! and changing it would be a breaking change. Instead, the API immediately turns the tree
-//! into strings, the lowest common denominator. However, exposing the tree directly
-//! would help projects that need to act on the information from the peer ID,
-//! as they would be able to work directly with the structures instead of re-parsing strings.
 
 use crate::client::Client;
-use crate::errors::{ClientParsingError, VersionParsingError};
+pub use crate::client_styles::Style;
+use crate::errors::{ClientParsingError, EncodingError, VersionParsingError};
+use crate::version::Version;
+#[cfg(feature = "std")]
 use tdyne_peer_id::PeerId;
 
+#[cfg(feature = "std")]
+mod announce;
+#[cfg(feature = "std")]
+mod anomaly;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod candidates;
 mod client;
+mod client_info;
+#[cfg(feature = "std")]
+mod client_name;
 mod client_styles;
-///
+#[cfg(feature = "std")]
+mod cross_check;
+mod dht;
+/// Errors returned by the parser and the encoder.
 pub mod errors;
+#[cfg(feature = "std")]
+mod explain;
+#[cfg(feature = "std")]
+mod handshake;
 mod known_clients;
-mod version;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "serde")]
+mod serde_utils;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod unknown;
+pub mod version;
 mod version_utils;
 
+#[cfg(feature = "std")]
+pub use crate::announce::{
+    decode_peer_id, parse_announce, parse_announce_structured, peer_id_from_announce,
+};
+#[cfg(feature = "std")]
+pub use crate::anomaly::{detect_anomalies, Anomaly};
+#[cfg(feature = "std")]
+pub use crate::cache::{CacheStats, CachedParser};
+#[cfg(feature = "std")]
+pub use crate::candidates::{parse_candidates, Candidate, Confidence};
+#[cfg(feature = "std")]
+pub use crate::client::Step;
+pub use crate::client_info::{ClientInfo, Family, Platform};
+#[cfg(feature = "std")]
+pub use crate::client_name::{parse_client_name, parse_user_agent, ParsedClientName};
+#[cfg(feature = "std")]
+pub use crate::cross_check::{cross_check, cross_check_user_agent, Verdict};
+pub use crate::dht::{parse_dht_version, DhtVersion};
+#[cfg(feature = "std")]
+pub use crate::explain::{parse_explained, Explanation};
+#[cfg(feature = "std")]
+pub use crate::handshake::{parse_handshake, Extension, Handshake, Inconsistency};
+pub use crate::known_clients::KnownClient;
+#[cfg(feature = "std")]
+pub use crate::registry::{RegisteredClient, Registry, RegistryBuilder, RegistryParsed};
+#[cfg(feature = "std")]
+pub use crate::stats::{StatsSnapshot, SwarmStats, NO_VERSION};
+#[cfg(feature = "std")]
+pub use crate::unknown::{Unknown, UnknownCollector, UnknownEntry};
+
 /// Human-readable representation of the client and the version (if it exists) encoded
 /// in the parsed peer ID.
+#[cfg(feature = "std")]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parsed {
     /// Name of the client. Can include suffixes such as `(Dev)` or `(Beta)`.
     pub client: String,
@@ -104,15 +221,142 @@ pub struct Parsed {
 ///     r#"Ok(Parsed { client: "Transmission", version: Ok(Some("4.0.4")) })"#
 /// );
 /// ```
+#[cfg(feature = "std")]
 pub fn parse(peer_id: PeerId) -> Result<Parsed, ClientParsingError> {
+    parse_structured(peer_id).map(Parsed::from)
+}
+
+/// Structured representation of the client and the version (if it exists) encoded
+/// in the parsed peer ID. Unlike [`Parsed`], it doesn't allocate and can be matched on.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParsedStructured {
+    /// The recognised client. Clients that changed their encoding over time are still
+    /// mapped onto a single variant.
+    pub client: KnownClient,
+    /// Encoding convention the peer ID follows.
+    pub style: Style,
+    /// Version, if any. The outer `Result` encodes parsing errors, while the internal
+    /// `Option` is `None` if the recognised client doesn't encode a version.
+    /// [`Version::Unknown`] means that the version exists, but is not parsed yet.
+    pub version: Result<Option<Version>, VersionParsingError>,
+}
+
+#[cfg(feature = "std")]
+impl From<ParsedStructured> for Parsed {
+    fn from(value: ParsedStructured) -> Self {
+        Self {
+            client: value.client.to_string(),
+            version: value.version.map(|v| v.map(|x| x.to_string())),
+        }
+    }
+}
+
+/// Same as [`parse`], but returns [`ParsedStructured`] instead of strings.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::{parse_structured, KnownClient, Style};
+/// use tdyne_peer_id_registry::version::{ShadowVersion, Version};
+///
+/// let parsed = parse_structured(PeerId::from(b"T03I--008gY6iB6Aq27C")).unwrap();
+/// assert_eq!(parsed.client, KnownClient::BitTornado);
+/// assert_eq!(parsed.style, Style::Shadow);
+/// assert_eq!(
+///     parsed.version,
+///     Ok(Some(Version::Shadow(ShadowVersion::new(0, [Some(3), Some(18), None]))))
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_structured(peer_id: PeerId) -> Result<ParsedStructured, ClientParsingError> {
+    parse_structured_bytes(peer_id.0)
+}
+
+/// Same as [`parse_structured`], but takes the raw 20 bytes of the peer ID instead of
+/// a [`PeerId`]. Available without the `std` feature.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::{parse_structured_bytes, KnownClient, Style};
+///
+/// let parsed = parse_structured_bytes(*b"-TR4040-xxxxxxxxxxxx").unwrap();
+/// assert_eq!(parsed.client, KnownClient::Transmission);
+/// assert_eq!(parsed.style, Style::Azureus);
+/// ```
+pub fn parse_structured_bytes(peer_id: [u8; 20]) -> Result<ParsedStructured, ClientParsingError> {
     let client = Client::try_from(peer_id)?;
 
-    Ok(Parsed {
-        client: client.to_canonical().to_string(),
-        version: parse_version(client, peer_id),
+    Ok(ParsedStructured {
+        client: client.to_canonical(),
+        style: client.style(),
+        version: client.parse_version(peer_id),
     })
 }
 
-fn parse_version(client: Client, peer_id: PeerId) -> Result<Option<String>, VersionParsingError> {
-    Ok(client.parse_version(peer_id)?.map(|x| x.to_string()))
+/// Generates a peer ID for a known client. The inverse of [`parse`].
+///
+/// Only clients with a structured encoding (Azureus, Shad0w and Mainline styles) whose
+/// version format is known can be encoded, and the version has to be in the style that
+/// the client uses. Bytes that aren't part of the client's header are copied from `random`
+/// as is, so pass 20 random bytes there.
+///
+/// Every peer ID returned by `encode` parses back into exactly the same client and version.
+/// Versions that can't survive the round trip (e.g. a Shad0w-style version ending in zero,
+/// which the parser treats as a release type designator) are rejected with
+/// [`EncodingError::UnrepresentableVersion`].
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::{encode, parse, KnownClient};
+/// use tdyne_peer_id_registry::version::Transmission;
+///
+/// let peer_id = encode(
+///     KnownClient::Transmission,
+///     Some(Transmission::Current(4, 0, 4, None).into()),
+///     *b"xxxxxxxxxxxxxxxxxxxx",
+/// )
+/// .expect("Transmission uses the Transmission version style");
+/// assert_eq!(&peer_id.0, b"-TR4040-xxxxxxxxxxxx");
+///
+/// let parsed = parse(peer_id).unwrap();
+/// assert_eq!(parsed.client, "Transmission");
+/// assert_eq!(parsed.version, Ok(Some("4.0.4".to_string())));
+/// ```
+#[cfg(feature = "std")]
+pub fn encode(
+    client: KnownClient,
+    version: Option<Version>,
+    random: [u8; 20],
+) -> Result<PeerId, EncodingError> {
+    encode_bytes(client, version, random).map(PeerId::from)
+}
+
+/// Same as [`encode`], but returns the raw 20 bytes of the peer ID instead of a [`PeerId`].
+/// Available without the `std` feature.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id_registry::{encode_bytes, KnownClient};
+/// use tdyne_peer_id_registry::version::Transmission;
+///
+/// let peer_id = encode_bytes(
+///     KnownClient::Transmission,
+///     Some(Transmission::Current(4, 0, 4, None).into()),
+///     *b"xxxxxxxxxxxxxxxxxxxx",
+/// )
+/// .expect("Transmission uses the Transmission version style");
+/// assert_eq!(&peer_id, b"-TR4040-xxxxxxxxxxxx");
+/// ```
+pub fn encode_bytes(
+    client: KnownClient,
+    version: Option<Version>,
+    random: [u8; 20],
+) -> Result<[u8; 20], EncodingError> {
+    Client::encode(client, version.as_ref(), random)
 }
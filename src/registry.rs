@@ -0,0 +1,255 @@
+// a registry that extends the built-in tables with clients registered at runtime,
+// for in-house clients and for clients that appeared after the crate was released
+
+use crate::client::{has_spoof_marker, Client};
+use crate::client_styles::azureus::Azureus;
+use crate::client_styles::shadow::Shadow;
+use crate::client_styles::types::ClientStyle;
+use crate::client_styles::Style;
+use crate::errors::{ClientParsingError, RegistryError, VersionParsingError};
+use crate::known_clients::KnownClient;
+use crate::version::{AzureusVersionStyle, Version};
+use crate::Parsed;
+use std::collections::HashMap;
+use std::fmt;
+use tdyne_peer_id::PeerId;
+
+#[cfg(feature = "overlay")]
+mod overlay;
+
+/// Client recognised by a [`Registry`], either a built-in one or one registered at runtime.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum RegisteredClient<'r> {
+    /// A client from the built-in tables.
+    Known(KnownClient),
+    /// A client registered with [`RegistryBuilder`], identified by its display name.
+    Custom(&'r str),
+}
+
+impl fmt::Display for RegisteredClient<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Known(client) => client.fmt(f),
+            Self::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Same as [`ParsedStructured`](crate::ParsedStructured), but returned by
+/// [`Registry::parse_structured`] and can refer to runtime-registered clients.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RegistryParsed<'r> {
+    /// The recognised client.
+    pub client: RegisteredClient<'r>,
+    /// Encoding convention the peer ID follows.
+    pub style: Style,
+    /// Version, if any. See [`ParsedStructured::version`](crate::ParsedStructured::version).
+    pub version: Result<Option<Version>, VersionParsingError>,
+}
+
+impl<'r> RegistryParsed<'r> {
+    fn custom(
+        name: &'r str,
+        style: Style,
+        version: Result<Option<Version>, VersionParsingError>,
+    ) -> Self {
+        Self {
+            client: RegisteredClient::Custom(name),
+            style,
+            version,
+        }
+    }
+}
+
+impl From<RegistryParsed<'_>> for Parsed {
+    fn from(value: RegistryParsed<'_>) -> Self {
+        Self {
+            client: value.client.to_string(),
+            version: value.version.map(|v| v.map(|x| x.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SubstringRule {
+    substring: Vec<u8>,
+    offset: usize,
+    name: String,
+    version: Option<Version>,
+}
+
+/// The built-in tables extended with clients registered at runtime. Create one with
+/// [`Registry::builder`].
+///
+/// Runtime-registered Azureus and Shad0w tags take precedence over the built-in ones,
+/// and so do substring rules over the built-in substring rules. Like the built-in
+/// substring rules, they are only tried if the peer ID doesn't follow any of the
+/// structured styles.
+///
+/// With the `overlay` feature, registrations can also be loaded from TOML or JSON files,
+/// see [`RegistryBuilder::overlay_file`](RegistryBuilder#method.overlay_file).
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::{Registry, RegisteredClient};
+/// use tdyne_peer_id_registry::version::{AzureusVersionStyle, ThreeBase16};
+///
+/// let registry = Registry::builder()
+///     .azureus(*b"XY", "In-house client", AzureusVersionStyle::ThreeBase16)
+///     .build()
+///     .expect("valid registrations");
+///
+/// let parsed = registry.parse_structured(PeerId::from(b"-XY1230-xxxxxxxxxxxx")).unwrap();
+/// assert_eq!(parsed.client, RegisteredClient::Custom("In-house client"));
+/// assert_eq!(parsed.version, Ok(Some(ThreeBase16(1, 2, 3).into())));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    azureus: HashMap<[u8; 2], (String, AzureusVersionStyle)>,
+    shadow: HashMap<u8, String>,
+    substrings: Vec<SubstringRule>,
+}
+
+impl Registry {
+    /// Starts a registry from the built-in tables.
+    pub fn builder() -> RegistryBuilder {
+        RegistryBuilder::default()
+    }
+
+    /// Same as [`parse_structured`](crate::parse_structured), but also recognises
+    /// runtime-registered clients.
+    pub fn parse_structured(
+        &self,
+        peer_id: PeerId,
+    ) -> Result<RegistryParsed<'_>, ClientParsingError> {
+        // the order of the checks mirrors `Client::try_from()`
+        if !has_spoof_marker(peer_id.0) {
+            if Azureus::does_match(peer_id.0) {
+                if let Some((name, kind)) = self.azureus.get(&[peer_id.0[1], peer_id.0[2]]) {
+                    return Ok(RegistryParsed::custom(
+                        name,
+                        Style::Azureus,
+                        kind.parse(peer_id.0),
+                    ));
+                }
+            } else if Shadow::has_header(peer_id.0) {
+                if let Some(name) = self.shadow.get(&peer_id.0[0]) {
+                    return Ok(RegistryParsed::custom(
+                        name,
+                        Style::Shadow,
+                        Shadow::parse_any_version(peer_id.0),
+                    ));
+                }
+            }
+        }
+
+        // peer IDs of a structured style with an unknown tag don't fall back to substrings
+        let builtin = Client::try_from(peer_id.0);
+        if matches!(
+            builtin,
+            Ok(Client::Substring(_)) | Err(ClientParsingError::UnknownClient)
+        ) {
+            let rule = self
+                .substrings
+                .iter()
+                .find(|r| peer_id.0[r.offset..].starts_with(&r.substring));
+            if let Some(rule) = rule {
+                return Ok(RegistryParsed::custom(
+                    &rule.name,
+                    Style::Substring,
+                    Ok(rule.version.clone()),
+                ));
+            }
+        }
+
+        builtin.map(|client| RegistryParsed {
+            client: RegisteredClient::Known(client.to_canonical()),
+            style: client.style(),
+            version: client.parse_version(peer_id.0),
+        })
+    }
+
+    /// Same as [`parse`](crate::parse), but also recognises runtime-registered clients.
+    pub fn parse(&self, peer_id: PeerId) -> Result<Parsed, ClientParsingError> {
+        self.parse_structured(peer_id).map(Parsed::from)
+    }
+}
+
+/// Builder for [`Registry`]. Registration errors are reported by [`RegistryBuilder::build`].
+#[derive(Debug, Clone, Default)]
+pub struct RegistryBuilder {
+    registry: Registry,
+    error: Option<RegistryError>,
+}
+
+impl RegistryBuilder {
+    fn fail(&mut self, error: RegistryError) {
+        self.error.get_or_insert(error);
+    }
+
+    /// Registers an Azureus-style client, `-XY1234-`, with its two-byte tag and version style.
+    pub fn azureus(
+        mut self,
+        tag: [u8; 2],
+        name: impl Into<String>,
+        style: AzureusVersionStyle,
+    ) -> Self {
+        if self
+            .registry
+            .azureus
+            .insert(tag, (name.into(), style))
+            .is_some()
+        {
+            self.fail(RegistryError::DuplicateAzureusTag(tag));
+        }
+        self
+    }
+
+    /// Registers a Shad0w-style client, `X1234-`, with its one-byte tag. The tag has to be
+    /// a printable ASCII character other than `-`.
+    pub fn shadow(mut self, tag: u8, name: impl Into<String>) -> Self {
+        if !tag.is_ascii_graphic() || tag == b'-' {
+            self.fail(RegistryError::InvalidShadowTag(tag));
+        } else if self.registry.shadow.insert(tag, name.into()).is_some() {
+            self.fail(RegistryError::DuplicateShadowTag(tag));
+        }
+        self
+    }
+
+    /// Registers a client recognised by a substring at a fixed offset. Every peer ID that
+    /// matches is reported with `version`. Rules are tried in the order of registration.
+    pub fn substring(
+        mut self,
+        substring: &[u8],
+        offset: usize,
+        name: impl Into<String>,
+        version: Option<Version>,
+    ) -> Self {
+        // written so that it can't overflow with huge offsets
+        if substring.is_empty() || substring.len() > 20 || offset > 20 - substring.len() {
+            self.fail(RegistryError::SubstringOutOfBounds(offset, substring.len()));
+        } else {
+            self.registry.substrings.push(SubstringRule {
+                substring: substring.to_vec(),
+                offset,
+                name: name.into(),
+                version,
+            });
+        }
+        self
+    }
+
+    /// Returns the registry, or the first registration error.
+    pub fn build(self) -> Result<Registry, RegistryError> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.registry),
+        }
+    }
+}
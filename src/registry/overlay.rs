@@ -0,0 +1,170 @@
+// overlays are files with the same shape as the builder calls, so that operators can
+// register clients without recompiling
+
+use crate::errors::{OverlayEntry, OverlayError};
+use crate::registry::RegistryBuilder;
+use crate::version::{AzureusVersionStyle, Version};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Overlay {
+    azureus: Vec<AzureusEntry>,
+    shadow: Vec<ShadowEntry>,
+    substring: Vec<SubstringEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AzureusEntry {
+    tag: String,
+    name: String,
+    style: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShadowEntry {
+    tag: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubstringEntry {
+    substring: String,
+    #[serde(default)]
+    offset: usize,
+    name: String,
+    #[serde(default)]
+    version: SubstringVersion,
+}
+
+// fixed versions need a `&'static str`, so overlays only get the two simple options
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SubstringVersion {
+    #[default]
+    None,
+    Unknown,
+}
+
+impl RegistryBuilder {
+    /// Registers the clients listed in a TOML overlay. An overlay has up to three sections,
+    /// each a list of entries that mirror the builder methods:
+    ///
+    /// ```toml
+    /// [[azureus]]
+    /// tag = "XY"
+    /// name = "In-house client"
+    /// style = "ThreeBase16"    # an AzureusVersionStyle variant
+    ///
+    /// [[shadow]]
+    /// tag = "Z"
+    /// name = "Another in-house client"
+    ///
+    /// [[substring]]
+    /// substring = "FOOBAR"
+    /// offset = 0               # optional, defaults to 0
+    /// name = "Yet another one"
+    /// version = "unknown"      # optional, "none" (the default) or "unknown"
+    /// ```
+    ///
+    /// Entries override the built-in clients with the same tags, which can be used to
+    /// change the name or the version style of a built-in client.
+    ///
+    /// Available with the `overlay` feature.
+    pub fn overlay_toml(self, overlay: &str) -> Result<Self, OverlayError> {
+        let overlay = toml::from_str(overlay).map_err(|e| OverlayError::Syntax(e.to_string()))?;
+        self.overlay(overlay)
+    }
+
+    /// Same as [`RegistryBuilder::overlay_toml`], but for JSON overlays with the same
+    /// structure: an object with `azureus`, `shadow` and `substring` arrays.
+    ///
+    /// Available with the `overlay` feature.
+    pub fn overlay_json(self, overlay: &str) -> Result<Self, OverlayError> {
+        let overlay =
+            serde_json::from_str(overlay).map_err(|e| OverlayError::Syntax(e.to_string()))?;
+        self.overlay(overlay)
+    }
+
+    /// Reads an overlay from a `.toml` or `.json` file, see
+    /// [`RegistryBuilder::overlay_toml`] for the format.
+    ///
+    /// Available with the `overlay` feature.
+    pub fn overlay_file(self, path: impl AsRef<Path>) -> Result<Self, OverlayError> {
+        let path = path.as_ref();
+        let is_toml = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => true,
+            Some("json") => false,
+            _ => return Err(OverlayError::UnsupportedFormat),
+        };
+
+        let contents = std::fs::read_to_string(path).map_err(|e| OverlayError::Io(e.kind()))?;
+
+        if is_toml {
+            self.overlay_toml(&contents)
+        } else {
+            self.overlay_json(&contents)
+        }
+    }
+
+    fn overlay(mut self, overlay: Overlay) -> Result<Self, OverlayError> {
+        // if the builder already failed, the error belongs to an earlier registration and
+        // build() reports it
+        let clean = self.error.is_none();
+
+        for (index, e) in overlay.azureus.into_iter().enumerate() {
+            let entry = OverlayEntry {
+                section: "azureus",
+                index,
+            };
+            let tag: [u8; 2] = e
+                .tag
+                .as_bytes()
+                .try_into()
+                .map_err(|_| OverlayError::InvalidTag(entry, e.tag.clone()))?;
+            let style: AzureusVersionStyle = e
+                .style
+                .parse()
+                .map_err(|_| OverlayError::UnknownVersionStyle(entry, e.style.clone()))?;
+            self = self.azureus(tag, e.name, style).attribute(clean, entry)?;
+        }
+
+        for (index, e) in overlay.shadow.into_iter().enumerate() {
+            let entry = OverlayEntry {
+                section: "shadow",
+                index,
+            };
+            let [tag] = e.tag.as_bytes() else {
+                return Err(OverlayError::InvalidTag(entry, e.tag));
+            };
+            self = self.shadow(*tag, e.name).attribute(clean, entry)?;
+        }
+
+        for (index, e) in overlay.substring.into_iter().enumerate() {
+            let entry = OverlayEntry {
+                section: "substring",
+                index,
+            };
+            let version = match e.version {
+                SubstringVersion::None => None,
+                SubstringVersion::Unknown => Some(Version::Unknown),
+            };
+            self = self
+                .substring(e.substring.as_bytes(), e.offset, e.name, version)
+                .attribute(clean, entry)?;
+        }
+
+        Ok(self)
+    }
+
+    fn attribute(self, clean: bool, entry: OverlayEntry) -> Result<Self, OverlayError> {
+        match self.error {
+            Some(e) if clean => Err(OverlayError::Registry(entry, e)),
+            _ => Ok(self),
+        }
+    }
+}
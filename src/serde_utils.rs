@@ -0,0 +1,70 @@
+// raw bytes in errors are serialized both as escaped ASCII, for humans reading logs,
+// and as hex, which is unambiguous and is what deserialization reads back
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Serialize, Deserialize)]
+struct Repr {
+    ascii: String,
+    hex: String,
+}
+
+fn serialize_slice<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    Repr {
+        ascii: bytes.escape_ascii().to_string(),
+        hex: bytes.iter().map(|b| format!("{b:02x}")).collect(),
+    }
+    .serialize(serializer)
+}
+
+fn deserialize_array<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    let repr = Repr::deserialize(deserializer)?;
+    let hex = repr.hex.as_bytes();
+    if hex.len() != N * 2 {
+        return Err(D::Error::invalid_length(
+            hex.len(),
+            &"two hex digits per byte",
+        ));
+    }
+
+    let mut bytes = [0; N];
+    for (b, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+        *b = std::str::from_utf8(pair)
+            .ok()
+            .and_then(|s| u8::from_str_radix(s, 16).ok())
+            .ok_or_else(|| D::Error::custom("invalid hex digit"))?;
+    }
+    Ok(bytes)
+}
+
+pub(crate) mod byte {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(b: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_slice(&[*b], serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        deserialize_array::<D, 1>(deserializer).map(|[b]| b)
+    }
+}
+
+pub(crate) mod bytes {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serialize_slice(bytes, serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        deserialize_array(deserializer)
+    }
+}
@@ -0,0 +1,337 @@
+// client distribution of a swarm, the thing dashboards built on this crate show
+
+use crate::errors::{ClientParsingError, VersionParsingError};
+use crate::known_clients::KnownClient;
+use crate::version::Version;
+use crate::{parse_structured, ParsedStructured};
+use std::collections::{BTreeMap, HashMap};
+use tdyne_peer_id::PeerId;
+
+/// Version key of [`StatsSnapshot::versions`] for clients that don't encode a version.
+pub const NO_VERSION: &str = "[no version]";
+
+/// Aggregates parse results into per-client, per-version, per-family and per-error counts.
+///
+/// Aggregators of sharded workers can be combined with [`SwarmStats::merge`]. Use
+/// [`SwarmStats::snapshot`] to export the counts, and [`StatsSnapshot::merge`] to combine
+/// snapshots exported by separate processes.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::SwarmStats;
+///
+/// let mut stats = SwarmStats::default();
+/// stats.add_peer_id(PeerId::from(b"-TR4040-xxxxxxxxxxxx"));
+/// stats.add_peer_id(PeerId::from(b"-TR4040-yyyyyyyyyyyy"));
+/// stats.add_peer_id(PeerId::from(b"-ZZ1234-xxxxxxxxxxxx"));
+///
+/// let snapshot = stats.snapshot();
+/// assert_eq!(snapshot.total, 3);
+/// assert_eq!(snapshot.clients["transmission"], 2);
+/// assert_eq!(snapshot.versions["transmission"]["4.0.4"], 2);
+/// assert_eq!(snapshot.errors["UnknownAzureusStylePrefix"], 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SwarmStats {
+    total: u64,
+    versions: HashMap<(KnownClient, Option<Version>), u64>,
+    version_errors: HashMap<(KnownClient, &'static str), u64>,
+    errors: HashMap<&'static str, u64>,
+}
+
+impl SwarmStats {
+    /// Parses a peer ID and counts the result.
+    pub fn add_peer_id(&mut self, peer_id: PeerId) {
+        self.add_result(&parse_structured(peer_id));
+    }
+
+    /// Counts a result of [`parse_structured`](crate::parse_structured), e.g. one that was
+    /// already parsed for other purposes.
+    pub fn add_result(&mut self, result: &Result<ParsedStructured, ClientParsingError>) {
+        self.total += 1;
+        match result {
+            Ok(parsed) => match &parsed.version {
+                Ok(v) => *self.versions.entry((parsed.client, v.clone())).or_default() += 1,
+                Err(e) => {
+                    *self
+                        .version_errors
+                        .entry((parsed.client, version_error_kind(e)))
+                        .or_default() += 1
+                }
+            },
+            Err(e) => *self.errors.entry(client_error_kind(e)).or_default() += 1,
+        }
+    }
+
+    /// Adds the counts of another aggregator, e.g. one of a different worker.
+    pub fn merge(&mut self, other: &SwarmStats) {
+        self.total += other.total;
+        for (k, v) in &other.versions {
+            *self.versions.entry(k.clone()).or_default() += v;
+        }
+        for (k, v) in &other.version_errors {
+            *self.version_errors.entry(*k).or_default() += v;
+        }
+        for (k, v) in &other.errors {
+            *self.errors.entry(k).or_default() += v;
+        }
+    }
+
+    /// Number of results counted so far.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Exports the counts. With the `serde` feature, the snapshot can be serialized,
+    /// e.g. to JSON.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let mut snapshot = StatsSnapshot {
+            total: self.total,
+            ..StatsSnapshot::default()
+        };
+
+        for ((client, version), count) in &self.versions {
+            snapshot.add_client(*client, *count);
+            let version = match version {
+                Some(v) => v.to_string(),
+                None => NO_VERSION.to_string(),
+            };
+            *snapshot
+                .versions
+                .entry(client.id().to_string())
+                .or_default()
+                .entry(version)
+                .or_default() += count;
+        }
+
+        for ((client, kind), count) in &self.version_errors {
+            snapshot.add_client(*client, *count);
+            *snapshot
+                .version_errors
+                .entry(client.id().to_string())
+                .or_default()
+                .entry(kind.to_string())
+                .or_default() += count;
+        }
+
+        for (kind, count) in &self.errors {
+            snapshot.errors.insert(kind.to_string(), *count);
+        }
+
+        snapshot
+    }
+}
+
+/// Counts exported by [`SwarmStats::snapshot`]. Clients are keyed by
+/// [`KnownClient::id`], families by [`Family::id`](crate::Family::id) and errors by
+/// the names of the error variants, e.g. `UnknownAzureusStylePrefix`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatsSnapshot {
+    /// Number of counted results, including errors.
+    pub total: u64,
+    /// Recognised peer IDs per client.
+    pub clients: BTreeMap<String, u64>,
+    /// Recognised peer IDs per client and version. Versions are keyed by their display
+    /// form, or [`NO_VERSION`]. Peer IDs with version errors aren't included.
+    pub versions: BTreeMap<String, BTreeMap<String, u64>>,
+    /// Recognised peer IDs per [`Family`](crate::Family). Clients without a family
+    /// aren't included.
+    pub families: BTreeMap<String, u64>,
+    /// Unrecognised peer IDs per [`ClientParsingError`] variant.
+    pub errors: BTreeMap<String, u64>,
+    /// Recognised peer IDs whose version couldn't be parsed, per client and
+    /// [`VersionParsingError`] variant.
+    pub version_errors: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+impl StatsSnapshot {
+    /// Adds the counts of another snapshot, e.g. one exported by a worker in a different
+    /// process. Merging the snapshots of two aggregators gives the same result as
+    /// merging the aggregators with [`SwarmStats::merge`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use tdyne_peer_id::PeerId;
+    /// use tdyne_peer_id_registry::SwarmStats;
+    ///
+    /// let mut first = SwarmStats::default();
+    /// first.add_peer_id(PeerId::from(b"-TR4040-xxxxxxxxxxxx"));
+    /// let mut second = SwarmStats::default();
+    /// second.add_peer_id(PeerId::from(b"-TR4040-yyyyyyyyyyyy"));
+    ///
+    /// let mut snapshot = first.snapshot();
+    /// snapshot.merge(&second.snapshot());
+    /// assert_eq!(snapshot.total, 2);
+    /// assert_eq!(snapshot.versions["transmission"]["4.0.4"], 2);
+    /// ```
+    pub fn merge(&mut self, other: &StatsSnapshot) {
+        self.total += other.total;
+        merge_counts(&mut self.clients, &other.clients);
+        for (client, versions) in &other.versions {
+            merge_counts(self.versions.entry(client.clone()).or_default(), versions);
+        }
+        merge_counts(&mut self.families, &other.families);
+        merge_counts(&mut self.errors, &other.errors);
+        for (client, kinds) in &other.version_errors {
+            merge_counts(
+                self.version_errors.entry(client.clone()).or_default(),
+                kinds,
+            );
+        }
+    }
+
+    fn add_client(&mut self, client: KnownClient, count: u64) {
+        *self.clients.entry(client.id().to_string()).or_default() += count;
+        if let Some(family) = client.family() {
+            *self.families.entry(family.id().to_string()).or_default() += count;
+        }
+    }
+}
+
+fn merge_counts(into: &mut BTreeMap<String, u64>, from: &BTreeMap<String, u64>) {
+    for (k, v) in from {
+        *into.entry(k.clone()).or_default() += v;
+    }
+}
+
+fn client_error_kind(e: &ClientParsingError) -> &'static str {
+    match e {
+        ClientParsingError::UnknownAzureusStylePrefix(_) => "UnknownAzureusStylePrefix",
+        ClientParsingError::UnknownShadowStylePrefix(_) => "UnknownShadowStylePrefix",
+        ClientParsingError::UnknownMainlineClientPrefix(_) => "UnknownMainlineClientPrefix",
+        ClientParsingError::UnknownClient => "UnknownClient",
+    }
+}
+
+fn version_error_kind(e: &VersionParsingError) -> &'static str {
+    match e {
+        VersionParsingError::DigitNotBase10(_) => "DigitNotBase10",
+        VersionParsingError::DigitNotBase16(_) => "DigitNotBase16",
+        VersionParsingError::DigitNotBase62(_) => "DigitNotBase62",
+        VersionParsingError::VersionOverflow => "VersionOverflow",
+        VersionParsingError::UnknownTransmissionReleaseType(_) => "UnknownTransmissionReleaseType",
+        VersionParsingError::UnexpectedBitCometBitLordHeader(..) => {
+            "UnexpectedBitCometBitLordHeader"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const PEER_IDS: &[&[u8; 20]] = &[
+        b"-TR4040-xxxxxxxxxxxx",
+        b"-TR4040-yyyyyyyyyyyy",
+        b"-TR3000-xxxxxxxxxxxx",
+        b"-qB4630-xxxxxxxxxxxx",
+        b"-lt0D80-xxxxxxxxxxxx",
+        b"-qBx630-xxxxxxxxxxxx",
+        b"-ZZ1234-xxxxxxxxxxxx",
+        b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+    ];
+
+    fn map<V>(entries: &[(&str, V)]) -> BTreeMap<String, V>
+    where
+        V: Clone,
+    {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    // all of PEER_IDS in one aggregator, and split between two shards
+    fn whole_and_shards() -> (SwarmStats, [SwarmStats; 2]) {
+        let mut whole = SwarmStats::default();
+        let mut shards = [SwarmStats::default(), SwarmStats::default()];
+        for (i, peer_id) in PEER_IDS.iter().enumerate() {
+            whole.add_peer_id(PeerId::from(*peer_id));
+            shards[i % 2].add_peer_id(PeerId::from(*peer_id));
+        }
+        (whole, shards)
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let (stats, _) = whole_and_shards();
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.total, 8);
+        assert_eq!(
+            snapshot.clients,
+            map(&[
+                ("libtorrentrakshasa", 1),
+                ("qbittorrent", 2),
+                ("transmission", 3)
+            ])
+        );
+        assert_eq!(
+            snapshot.versions["transmission"],
+            map(&[("3.0.0", 1), ("4.0.4", 2)])
+        );
+        assert_eq!(snapshot.families, map(&[("libtorrent", 2)]));
+        assert_eq!(
+            snapshot.errors,
+            map(&[("UnknownAzureusStylePrefix", 1), ("UnknownClient", 1)])
+        );
+        assert_eq!(
+            snapshot.version_errors,
+            map(&[("qbittorrent", map(&[("DigitNotBase16", 1)]))])
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let (whole, shards) = whole_and_shards();
+
+        let [mut merged, other] = shards;
+        merged.merge(&other);
+        assert_eq!(merged.total(), whole.total());
+        assert_eq!(merged.snapshot(), whole.snapshot());
+    }
+
+    #[test]
+    fn test_merge_snapshots() {
+        let (whole, shards) = whole_and_shards();
+
+        let mut merged = shards[0].snapshot();
+        merged.merge(&shards[1].snapshot());
+        assert_eq!(merged, whole.snapshot());
+    }
+
+    // shards in separate processes exchange serialized snapshots
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_merge_deserialized() {
+        let (whole, shards) = whole_and_shards();
+
+        let [first, second] = shards.map(|s| serde_json::to_string(&s.snapshot()).unwrap());
+        let mut merged: StatsSnapshot = serde_json::from_str(&first).unwrap();
+        merged.merge(&serde_json::from_str(&second).unwrap());
+        assert_eq!(merged, whole.snapshot());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json() {
+        let mut stats = SwarmStats::default();
+        stats.add_peer_id(PeerId::from(b"-qB4630-xxxxxxxxxxxx"));
+        stats.add_peer_id(PeerId::from(b"-qBx630-xxxxxxxxxxxx"));
+        stats.add_peer_id(PeerId::from(b"-ZZ1234-xxxxxxxxxxxx"));
+
+        let json = serde_json::to_string(&stats.snapshot()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"total":3,"clients":{"qbittorrent":2},"versions":{"qbittorrent":{"4.6.3":1}},"families":{"libtorrent":2},"errors":{"UnknownAzureusStylePrefix":1},"version_errors":{"qbittorrent":{"DigitNotBase16":1}}}"#
+        );
+        let parsed: StatsSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, stats.snapshot());
+    }
+}
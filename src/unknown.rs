@@ -0,0 +1,306 @@
+// collects what the registry doesn't know yet, so that it can be reported upstream
+
+use crate::errors::ClientParsingError;
+use crate::known_clients::KnownClient;
+use crate::version::Version;
+use crate::{parse_structured, ParsedStructured};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write as _;
+use tdyne_peer_id::PeerId;
+
+/// Something the registry doesn't recognise, tallied by [`UnknownCollector`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Unknown {
+    /// See [`ClientParsingError::UnknownAzureusStylePrefix`].
+    AzureusPrefix([u8; 2]),
+    /// See [`ClientParsingError::UnknownShadowStylePrefix`].
+    ShadowPrefix(u8),
+    /// See [`ClientParsingError::UnknownMainlineClientPrefix`].
+    MainlinePrefix([u8; 4]),
+    /// The client is known, but its version encoding isn't, see [`Version::Unknown`].
+    Version(KnownClient),
+}
+
+impl fmt::Display for Unknown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AzureusPrefix(tag) => write!(f, "Azureus-style prefix {}", escape(tag)),
+            Self::ShadowPrefix(tag) => write!(f, "Shad0w-style prefix {}", escape(&[*tag])),
+            Self::MainlinePrefix(tag) => write!(f, "Mainline-style prefix {}", escape(tag)),
+            Self::Version(client) => write!(f, "version of {client}"),
+        }
+    }
+}
+
+/// Counts and sample peer IDs of one [`Unknown`], returned by
+/// [`UnknownCollector::entries`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnknownEntry {
+    /// What wasn't recognised.
+    pub unknown: Unknown,
+    /// How many times it was seen.
+    pub count: u64,
+    /// The first distinct peer IDs it was seen in.
+    pub samples: Vec<[u8; 20]>,
+}
+
+/// Tallies unknown client prefixes and unknown version encodings together with sample
+/// peer IDs, and formats them as a GitHub issue for the registry.
+///
+/// Example:
+///
+/// ```
+/// use tdyne_peer_id::PeerId;
+/// use tdyne_peer_id_registry::{Unknown, UnknownCollector};
+///
+/// let mut collector = UnknownCollector::new(3);
+/// collector.add_peer_id(PeerId::from(b"-ZZ1234-xxxxxxxxxxxx"));
+/// collector.add_peer_id(PeerId::from(b"-TR4040-xxxxxxxxxxxx"));
+///
+/// let entries = collector.entries();
+/// assert_eq!(entries.len(), 1);
+/// assert_eq!(entries[0].unknown, Unknown::AzureusPrefix(*b"ZZ"));
+///
+/// // paste into https://github.com/torrentdyne/tdyne-peer-id-registry/issues/new
+/// let report = collector.report();
+/// ```
+#[derive(Debug, Clone)]
+pub struct UnknownCollector {
+    max_samples: usize,
+    entries: HashMap<Unknown, (u64, Vec<[u8; 20]>)>,
+}
+
+impl UnknownCollector {
+    /// Creates a collector that keeps up to `max_samples` sample peer IDs per entry.
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            max_samples,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Parses a peer ID and tallies it if the registry doesn't recognise it fully.
+    pub fn add_peer_id(&mut self, peer_id: PeerId) {
+        self.add_result(peer_id, &parse_structured(peer_id));
+    }
+
+    /// Same as [`UnknownCollector::add_peer_id`], but for an already parsed peer ID.
+    pub fn add_result(
+        &mut self,
+        peer_id: PeerId,
+        result: &Result<ParsedStructured, ClientParsingError>,
+    ) {
+        let unknown = match result {
+            Err(ClientParsingError::UnknownAzureusStylePrefix(tag)) => Unknown::AzureusPrefix(*tag),
+            Err(ClientParsingError::UnknownShadowStylePrefix(tag)) => Unknown::ShadowPrefix(*tag),
+            Err(ClientParsingError::UnknownMainlineClientPrefix(tag)) => {
+                Unknown::MainlinePrefix(*tag)
+            }
+            Ok(ParsedStructured {
+                client,
+                version: Ok(Some(Version::Unknown)),
+                ..
+            }) => Unknown::Version(*client),
+            // nothing to report without a prefix to point at
+            Err(ClientParsingError::UnknownClient) | Ok(_) => return,
+        };
+        self.add(unknown, 1, &[peer_id.0]);
+    }
+
+    fn add(&mut self, unknown: Unknown, count: u64, samples: &[[u8; 20]]) {
+        let (total, kept) = self.entries.entry(unknown).or_default();
+        *total += count;
+        for sample in samples {
+            if kept.len() >= self.max_samples {
+                break;
+            }
+            if !kept.contains(sample) {
+                kept.push(*sample);
+            }
+        }
+    }
+
+    /// Adds the tallies of another collector, e.g. one of a different worker.
+    pub fn merge(&mut self, other: &UnknownCollector) {
+        for (unknown, (count, samples)) in &other.entries {
+            self.add(*unknown, *count, samples);
+        }
+    }
+
+    /// Returns the tallies, most frequent first.
+    pub fn entries(&self) -> Vec<UnknownEntry> {
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(unknown, (count, samples))| UnknownEntry {
+                unknown: *unknown,
+                count: *count,
+                samples: samples.clone(),
+            })
+            .collect();
+        // ties are broken by the description to keep reports stable
+        entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.unknown.to_string().cmp(&b.unknown.to_string()))
+        });
+        entries
+    }
+
+    /// Formats the tallies as a Markdown report ready to be filed as a GitHub issue.
+    pub fn report(&self) -> String {
+        let entries = self.entries();
+        let (prefixes, versions): (Vec<_>, Vec<_>) = entries
+            .iter()
+            .partition(|e| !matches!(e.unknown, Unknown::Version(_)));
+
+        let mut report = format!(
+            "Unrecognised peer IDs collected with tdyne-peer-id-registry {}.\n",
+            env!("CARGO_PKG_VERSION")
+        );
+
+        // writing into a String never fails
+        if !prefixes.is_empty() {
+            report.push_str("\n### Unknown client prefixes\n\n");
+            report.push_str("| Style | Prefix | Count | Sample peer IDs |\n");
+            report.push_str("| --- | --- | ---: | --- |\n");
+            for e in prefixes {
+                let (style, prefix) = match e.unknown {
+                    Unknown::AzureusPrefix(tag) => ("Azureus", escape(&tag)),
+                    Unknown::ShadowPrefix(tag) => ("Shad0w", escape(&[tag])),
+                    Unknown::MainlinePrefix(tag) => ("Mainline", escape(&tag)),
+                    Unknown::Version(_) => unreachable!("partitioned above"),
+                };
+                let _ = writeln!(
+                    report,
+                    "| {style} | `{prefix}` | {} | {} |",
+                    e.count,
+                    samples(&e.samples)
+                );
+            }
+        }
+
+        if !versions.is_empty() {
+            report.push_str("\n### Clients with unknown version encodings\n\n");
+            report.push_str("| Client | Count | Sample peer IDs |\n");
+            report.push_str("| --- | ---: | --- |\n");
+            for e in versions {
+                let Unknown::Version(client) = e.unknown else {
+                    unreachable!("partitioned above")
+                };
+                let _ = writeln!(
+                    report,
+                    "| {client} | {} | {} |",
+                    e.count,
+                    samples(&e.samples)
+                );
+            }
+        }
+
+        report
+    }
+}
+
+// printable ASCII as is, everything else as `\xNN`. Backticks, pipes and backslashes are
+// escaped too, so that the result can be put in a Markdown table cell
+fn escape(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, &b| {
+        if b.is_ascii_graphic() && !matches!(b, b'`' | b'|' | b'\\') {
+            s.push(char::from(b));
+        } else {
+            let _ = write!(s, "\\x{b:02x}");
+        }
+        s
+    })
+}
+
+fn samples(samples: &[[u8; 20]]) -> String {
+    samples
+        .iter()
+        .map(|s| {
+            let hex = s.iter().fold(String::new(), |mut h, b| {
+                let _ = write!(h, "{b:02x}");
+                h
+            });
+            format!("`{}` (`{hex}`)", escape(s))
+        })
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn collect(peer_ids: &[&[u8; 20]]) -> UnknownCollector {
+        let mut collector = UnknownCollector::new(2);
+        for peer_id in peer_ids {
+            collector.add_peer_id(PeerId::from(*peer_id));
+        }
+        collector
+    }
+
+    #[test]
+    fn test_entries() {
+        let collector = collect(&[
+            b"-ZZ1234-aaaaaaaaaaaa",
+            b"-ZZ1234-bbbbbbbbbbbb",
+            b"-ZZ1234-aaaaaaaaaaaa",
+            b"-ZZ1234-cccccccccccc",
+            b"-TR4040-xxxxxxxxxxxx",
+            b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+        ]);
+        assert_eq!(
+            collector.entries(),
+            vec![UnknownEntry {
+                unknown: Unknown::AzureusPrefix(*b"ZZ"),
+                count: 4,
+                samples: vec![*b"-ZZ1234-aaaaaaaaaaaa", *b"-ZZ1234-bbbbbbbbbbbb"],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = collect(&[b"-ZZ1234-aaaaaaaaaaaa"]);
+        let b = collect(&[b"-ZZ1234-bbbbbbbbbbbb", b"-ZZ1234-cccccccccccc"]);
+        a.merge(&b);
+        let entries = a.entries();
+        assert_eq!(entries[0].count, 3);
+        assert_eq!(
+            entries[0].samples,
+            vec![*b"-ZZ1234-aaaaaaaaaaaa", *b"-ZZ1234-bbbbbbbbbbbb"]
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let collector = collect(&[
+            b"-ZZ1234-a|a`aaaaa\xffaa",
+            b"-ZZ1234-bbbbbbbbbbbb",
+            b"-BB1234-aaaaaaaaaaaa",
+        ]);
+        let report = collector.report();
+        let expected = format!(
+            "Unrecognised peer IDs collected with tdyne-peer-id-registry {}.\n\
+             \n\
+             ### Unknown client prefixes\n\
+             \n\
+             | Style | Prefix | Count | Sample peer IDs |\n\
+             | --- | --- | ---: | --- |\n\
+             | Azureus | `ZZ` | 2 | `-ZZ1234-a\\x7ca\\x60aaaaa\\xffaa` (`2d5a5a313233342d617c61606161616161ff6161`)<br>`-ZZ1234-bbbbbbbbbbbb` (`2d5a5a313233342d626262626262626262626262`) |\n\
+             \n\
+             ### Clients with unknown version encodings\n\
+             \n\
+             | Client | Count | Sample peer IDs |\n\
+             | --- | ---: | --- |\n\
+             | BitBuddy | 1 | `-BB1234-aaaaaaaaaaaa` (`2d4242313233342d616161616161616161616161`) |\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(report, expected);
+    }
+}
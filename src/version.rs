@@ -1,35 +1,137 @@
-use crate::client_styles::azureus;
-use crate::client_styles::mainline::MainlineVersion;
-use crate::client_styles::shadow::ShadowVersion;
-use std::fmt;
+//! Structured representation of the versions encoded in peer IDs.
+//!
+//! Every client style has its own version encoding, so [`Version`] is a tree of the
+//! encodings the library knows about. The same types are accepted by
+//! [`encode`](crate::encode).
+//!
+//! To compare versions, e.g. to find peers running an outdated release, convert them
+//! into [`NumericVersion`] with [`Version::numeric`]:
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! use tdyne_peer_id::PeerId;
+//! use tdyne_peer_id_registry::{parse_structured, KnownClient};
+//! use tdyne_peer_id_registry::version::NumericVersion;
+//!
+//! let parsed = parse_structured(PeerId::from(b"-TR2930-xxxxxxxxxxxx")).unwrap();
+//! assert_eq!(parsed.client, KnownClient::Transmission);
+//!
+//! let version = parsed.version.unwrap().unwrap().numeric().unwrap();
+//! assert!(version < NumericVersion::try_new(&[2, 94], None).unwrap());
+//! # }
+//! ```
 
-#[derive(Debug, Clone)]
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+pub use crate::client_styles::azureus::version_styles::four_base16::FourBase16;
+pub use crate::client_styles::azureus::version_styles::one_base10_unknown::OneBase10Unknown;
+pub use crate::client_styles::azureus::version_styles::three_base16::ThreeBase16;
+pub use crate::client_styles::azureus::version_styles::three_base16_alpha_beta::{
+    Suffix as AlphaBetaSuffix, ThreeBase16AlphaBeta,
+};
+pub use crate::client_styles::azureus::version_styles::transmission::{
+    Suffix as TransmissionSuffix, Transmission,
+};
+pub use crate::client_styles::azureus::version_styles::two_base16::TwoBase16;
+pub use crate::client_styles::azureus::version_styles::two_maj_two_min_base10::TwoMajTwoMinBase10;
+pub use crate::client_styles::azureus::version_styles::StyleKind as AzureusVersionStyle;
+pub use crate::client_styles::azureus::version_styles::Styles as AzureusVersion;
+pub use crate::client_styles::mainline::MainlineVersion;
+pub use crate::client_styles::shadow::ShadowVersion;
+
+/// Unofficial BitComet builds that change the peer ID prefix.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[non_exhaustive]
-pub(crate) enum BitCometMod {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BitCometMod {
+    /// Prefixed with `FUTB`.
     Solidox,
+    /// Prefixed with `xUTB`.
     Mod2,
 }
 
-#[derive(Debug, Clone)]
+/// Version encoded in a peer ID, grouped by the encoding style.
+///
+/// With the `serde` feature, versions implement `Serialize`, but not `Deserialize`:
+/// [`Version::Fixed`] borrows its name from the registry.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[non_exhaustive]
-pub(crate) enum Version {
-    Azureus(azureus::version_styles::Styles),
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Version {
+    /// See [`AzureusVersion`].
+    Azureus(AzureusVersion),
 
+    /// See [`ShadowVersion`].
     Shadow(ShadowVersion),
 
+    /// See [`MainlineVersion`].
     Mainline(MainlineVersion),
 
+    /// BitSpirit only encodes its major version.
     BitSpirit(u8),
+    /// Major and minor versions of BitComet or BitLord, plus the mod, if any.
     BitCometBitLord(u8, u8, Option<BitCometMod>),
 
     // for special cases when it's just one version
-    Fixed(&'static str),
+    /// Some clients are recognised by a fixed substring that also pins down the version.
+    /// The string is what the version is displayed as, the [`NumericVersion`] is `None`
+    /// if the version isn't numeric.
+    Fixed(&'static str, Option<NumericVersion>),
 
     // for cases when I know that a version is there, I just don't parse it yet
     // everything that uses it is a "todo"
+    /// The client does encode a version, but the library can't decode it yet.
     Unknown,
 }
 
+impl From<AzureusVersion> for Version {
+    fn from(value: AzureusVersion) -> Self {
+        Self::Azureus(value)
+    }
+}
+
+impl From<ShadowVersion> for Version {
+    fn from(value: ShadowVersion) -> Self {
+        Self::Shadow(value)
+    }
+}
+
+impl From<MainlineVersion> for Version {
+    fn from(value: MainlineVersion) -> Self {
+        Self::Mainline(value)
+    }
+}
+
+macro_rules! impl_from_azureus_style {
+    ($($style:ident),*) => {
+        $(
+            impl From<$style> for AzureusVersion {
+                fn from(value: $style) -> Self {
+                    Self::$style(value)
+                }
+            }
+
+            impl From<$style> for Version {
+                fn from(value: $style) -> Self {
+                    Self::Azureus(AzureusVersion::$style(value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_azureus_style!(
+    ThreeBase16,
+    ThreeBase16AlphaBeta,
+    FourBase16,
+    TwoMajTwoMinBase10,
+    Transmission,
+    OneBase10Unknown,
+    TwoBase16
+);
+
 impl fmt::Display for Version {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -45,8 +147,307 @@ impl fmt::Display for Version {
                 };
                 write!(f, "{v1}.{v2}{suffix}")
             }
-            Self::Fixed(x) => write!(f, "{x}"),
+            Self::Fixed(x, _) => write!(f, "{x}"),
             Self::Unknown => write!(f, "[unknown version]"),
         }
     }
 }
+
+impl Version {
+    /// Numeric representation of the version, for comparisons. Returns `None` for
+    /// [`Version::Unknown`] and for fixed versions that aren't numbers.
+    ///
+    /// Components the peer ID doesn't encode (e.g. everything after the major version
+    /// of [`OneBase10Unknown`]) are omitted, and thus compare as zeroes. BitComet mods
+    /// aren't part of the numeric version.
+    pub fn numeric(&self) -> Option<NumericVersion> {
+        match self {
+            Self::Azureus(x) => Some(x.numeric()),
+            Self::Shadow(ShadowVersion(v1, rest)) => {
+                let mut components = [*v1, 0, 0, 0];
+                let mut len = 1;
+                for v in rest.iter().map_while(|x| *x) {
+                    components[len] = v;
+                    len += 1;
+                }
+                Some(NumericVersion::new(&components[..len], None))
+            }
+            Self::Mainline(MainlineVersion(v1, v2, v3)) => {
+                Some(NumericVersion::new(&[*v1, *v2, *v3], None))
+            }
+            Self::BitSpirit(v1) => Some(NumericVersion::new(&[*v1], None)),
+            Self::BitCometBitLord(v1, v2, _) => Some(NumericVersion::new(&[*v1, *v2], None)),
+            Self::Fixed(_, numeric) => *numeric,
+            Self::Unknown => None,
+        }
+    }
+}
+
+impl AzureusVersion {
+    fn numeric(&self) -> NumericVersion {
+        match *self {
+            Self::ThreeBase16(ThreeBase16(v1, v2, v3)) => NumericVersion::new(&[v1, v2, v3], None),
+            Self::ThreeBase16AlphaBeta(ThreeBase16AlphaBeta(v1, v2, v3, s)) => {
+                let pre_release = s.map(|s| match s {
+                    AlphaBetaSuffix::Alpha => PreRelease::Alpha,
+                    AlphaBetaSuffix::Beta => PreRelease::Beta,
+                });
+                NumericVersion::new(&[v1, v2, v3], pre_release)
+            }
+            Self::FourBase16(FourBase16(v1, v2, v3, v4)) => {
+                NumericVersion::new(&[v1, v2, v3, v4], None)
+            }
+            Self::TwoMajTwoMinBase10(TwoMajTwoMinBase10(v1, v2)) => {
+                NumericVersion::new(&[v1, v2], None)
+            }
+            Self::Transmission(x) => {
+                let pre_release = |s: Option<TransmissionSuffix>| {
+                    s.map(|s| match s {
+                        TransmissionSuffix::Beta => PreRelease::Beta,
+                        TransmissionSuffix::Dev => PreRelease::Dev,
+                    })
+                };
+                match x {
+                    Transmission::Pre1(v) => NumericVersion::new(&[0, v], None),
+                    Transmission::Pre3(v1, v2, s) => NumericVersion::new(&[v1, v2], pre_release(s)),
+                    Transmission::Current(v1, v2, v3, s) => {
+                        NumericVersion::new(&[v1, v2, v3], pre_release(s))
+                    }
+                }
+            }
+            Self::OneBase10Unknown(OneBase10Unknown(v1)) => NumericVersion::new(&[v1], None),
+            Self::TwoBase16(TwoBase16(v1, v2)) => NumericVersion::new(&[v1, v2], None),
+        }
+    }
+}
+
+/// Pre-release channel of a [`NumericVersion`]. Channels are ordered from the least
+/// to the most stable.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PreRelease {
+    /// Development build, e.g. Transmission's `Z` release type.
+    Dev,
+    /// Alpha release.
+    Alpha,
+    /// Beta release.
+    Beta,
+    /// Release candidate.
+    ReleaseCandidate,
+}
+
+impl fmt::Display for PreRelease {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Dev => write!(f, "Dev"),
+            Self::Alpha => write!(f, "Alpha"),
+            Self::Beta => write!(f, "Beta"),
+            Self::ReleaseCandidate => write!(f, "RC"),
+        }
+    }
+}
+
+/// Version as a list of up to four numeric components plus an optional pre-release
+/// channel, derived from [`Version`] with [`Version::numeric`].
+///
+/// The ordering is total: components are compared left to right, with missing trailing
+/// components treated as zeroes (so `2.94` equals `2.94.0`), and a pre-release sorts
+/// before the release with the same components. Comparing versions of different clients
+/// compiles, but is meaningless.
+#[derive(Debug, Copy, Clone)]
+pub struct NumericVersion {
+    components: [u8; 4],
+    len: u8,
+    pre_release: Option<PreRelease>,
+}
+
+impl NumericVersion {
+    /// Creates a version from its components, most significant first, `None` if there
+    /// are no components or more than four of them.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use tdyne_peer_id_registry::version::{NumericVersion, PreRelease};
+    ///
+    /// let rc = NumericVersion::try_new(&[1, 7], Some(PreRelease::ReleaseCandidate)).unwrap();
+    /// assert_eq!(rc.components(), &[1, 7]);
+    /// assert!(rc < NumericVersion::try_new(&[1, 7], None).unwrap());
+    ///
+    /// assert_eq!(NumericVersion::try_new(&[], None), None);
+    /// assert_eq!(NumericVersion::try_new(&[1, 2, 3, 4, 5], None), None);
+    /// ```
+    pub const fn try_new(components: &[u8], pre_release: Option<PreRelease>) -> Option<Self> {
+        if components.is_empty() || components.len() > 4 {
+            return None;
+        }
+
+        let mut padded = [0; 4];
+        let mut i = 0;
+        while i < components.len() {
+            padded[i] = components[i];
+            i += 1;
+        }
+
+        Some(Self {
+            components: padded,
+            len: components.len() as u8,
+            pre_release,
+        })
+    }
+
+    // for components the caller knows to be one to four, including the generated code
+    pub(crate) const fn new(components: &[u8], pre_release: Option<PreRelease>) -> Self {
+        match Self::try_new(components, pre_release) {
+            Some(v) => v,
+            None => panic!("a numeric version has one to four components"),
+        }
+    }
+
+    /// Components of the version, most significant first, as encoded in the peer ID.
+    pub fn components(&self) -> &[u8] {
+        &self.components[..self.len as usize]
+    }
+
+    /// Pre-release channel, `None` for releases.
+    pub fn pre_release(&self) -> Option<PreRelease> {
+        self.pre_release
+    }
+
+    // releases sort after every pre-release of the same version
+    fn key(&self) -> ([u8; 4], bool, Option<PreRelease>) {
+        (
+            self.components,
+            self.pre_release.is_none(),
+            self.pre_release,
+        )
+    }
+}
+
+// the padding is an implementation detail, so only the meaningful components are written
+#[cfg(feature = "serde")]
+impl serde::Serialize for NumericVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("NumericVersion", 2)?;
+        s.serialize_field("components", self.components())?;
+        s.serialize_field("pre_release", &self.pre_release)?;
+        s.end()
+    }
+}
+
+impl PartialEq for NumericVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for NumericVersion {}
+
+impl Hash for NumericVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for NumericVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NumericVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl fmt::Display for NumericVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (first, rest) = self
+            .components()
+            .split_first()
+            .expect("at least one component");
+        write!(f, "{first}")?;
+        for v in rest {
+            write!(f, ".{v}")?;
+        }
+        if let Some(p) = self.pre_release {
+            write!(f, " ({p})")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::UnknownVersionStyle;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    const fn v(components: &[u8]) -> NumericVersion {
+        NumericVersion::new(components, None)
+    }
+
+    const fn pre(components: &[u8], p: PreRelease) -> NumericVersion {
+        NumericVersion::new(components, Some(p))
+    }
+
+    #[test_case(v(&[2, 93]), v(&[2, 94]))]
+    #[test_case(v(&[2, 94]), v(&[3, 0, 0]))]
+    #[test_case(v(&[2, 94]), v(&[2, 94, 1]))]
+    #[test_case(pre(&[4, 0, 4], PreRelease::Dev), v(&[4, 0, 4]))]
+    #[test_case(pre(&[4, 0, 4], PreRelease::Dev), pre(&[4, 0, 4], PreRelease::Alpha))]
+    #[test_case(pre(&[4, 0, 4], PreRelease::Alpha), pre(&[4, 0, 4], PreRelease::Beta))]
+    #[test_case(pre(&[1, 7, 0], PreRelease::Beta), pre(&[1, 7, 0], PreRelease::ReleaseCandidate))]
+    #[test_case(v(&[4, 0, 3]), pre(&[4, 0, 4], PreRelease::Dev))]
+    fn test_ordering(lower: NumericVersion, higher: NumericVersion) {
+        assert!(lower < higher);
+        assert!(higher > lower);
+        assert_ne!(lower, higher);
+    }
+
+    #[test_case(v(&[2, 94]), v(&[2, 94, 0]))]
+    #[test_case(v(&[5]), v(&[5, 0, 0, 0]))]
+    fn test_trailing_zeroes(a: NumericVersion, b: NumericVersion) {
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+    }
+
+    #[test_case(Transmission::Pre1(72).into(), Some(v(&[0, 72])))]
+    #[test_case(Transmission::Pre3(1, 33, Some(TransmissionSuffix::Beta)).into(), Some(pre(&[1, 33], PreRelease::Beta)))]
+    #[test_case(Transmission::Current(4, 0, 4, Some(TransmissionSuffix::Dev)).into(), Some(pre(&[4, 0, 4], PreRelease::Dev)))]
+    #[test_case(ThreeBase16AlphaBeta(3, 5, 5, Some(AlphaBetaSuffix::Alpha)).into(), Some(pre(&[3, 5, 5], PreRelease::Alpha)))]
+    #[test_case(FourBase16(5, 7, 0, 1).into(), Some(v(&[5, 7, 0, 1])))]
+    #[test_case(OneBase10Unknown(5).into(), Some(v(&[5])))]
+    #[test_case(ShadowVersion::new(0, [Some(3), Some(18), None]).into(), Some(v(&[0, 3, 18])))]
+    #[test_case(MainlineVersion::new(5, 0, 7).into(), Some(v(&[5, 0, 7])))]
+    #[test_case(Version::BitCometBitLord(1, 2, Some(BitCometMod::Mod2)), Some(v(&[1, 2])))]
+    #[test_case(Version::Fixed("II", None), None)]
+    #[test_case(Version::Unknown, None)]
+    fn test_numeric(version: Version, numeric: Option<NumericVersion>) {
+        let numeric_components = numeric.map(|n| n.components().to_vec());
+        let actual = version.numeric();
+        assert_eq!(actual, numeric);
+        // equality ignores trailing zeroes, so check the components explicitly
+        assert_eq!(actual.map(|n| n.components().to_vec()), numeric_components);
+    }
+
+    #[test_case(v(&[2, 94]), "2.94")]
+    #[test_case(pre(&[1, 7, 0], PreRelease::ReleaseCandidate), "1.7.0 (RC)")]
+    #[test_case(pre(&[4, 0, 4], PreRelease::Dev), "4.0.4 (Dev)")]
+    fn test_display(version: NumericVersion, s: &str) {
+        assert_eq!(version.to_string(), s);
+    }
+
+    #[test_case("ThreeBase16", Ok(AzureusVersionStyle::ThreeBase16))]
+    #[test_case("NoVersion", Ok(AzureusVersionStyle::NoVersion))]
+    #[test_case("threebase16", Err(UnknownVersionStyle))]
+    #[test_case("", Err(UnknownVersionStyle))]
+    fn test_style_from_str(s: &str, style: Result<AzureusVersionStyle, UnknownVersionStyle>) {
+        assert_eq!(s.parse(), style);
+    }
+}
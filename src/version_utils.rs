@@ -1,4 +1,4 @@
-use crate::errors::VersionParsingError;
+use crate::errors::{EncodingError, VersionParsingError};
 
 pub(crate) fn base16(b: u8) -> Result<u8, VersionParsingError> {
     match b {
@@ -32,6 +32,40 @@ pub(crate) fn base62(b: u8) -> Result<u8, VersionParsingError> {
     }
 }
 
+// the inverses of the above, used by the encoder
+
+pub(crate) fn to_base16(v: u8) -> Result<u8, EncodingError> {
+    match v {
+        0..=9 => Ok(b'0' + v),
+        10..=15 => Ok(b'A' + (v - 10)),
+        _ => Err(EncodingError::ComponentOutOfRange(v)),
+    }
+}
+
+pub(crate) fn to_base10(v: u8) -> Result<u8, EncodingError> {
+    match v {
+        0..=9 => Ok(b'0' + v),
+        _ => Err(EncodingError::ComponentOutOfRange(v)),
+    }
+}
+
+pub(crate) fn to_base62(v: u8) -> Result<u8, EncodingError> {
+    match v {
+        0..=9 => Ok(b'0' + v),
+        10..=35 => Ok(b'A' + (v - 10)),
+        36..=61 => Ok(b'a' + (v - 36)),
+        _ => Err(EncodingError::ComponentOutOfRange(v)),
+    }
+}
+
+// two base10 digits, for the styles that encode 0..=99 in two bytes
+pub(crate) fn to_two_base10(v: u8) -> Result<[u8; 2], EncodingError> {
+    if v > 99 {
+        return Err(EncodingError::ComponentOutOfRange(v));
+    }
+    Ok([to_base10(v / 10)?, to_base10(v % 10)?])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +114,26 @@ mod tests {
             assert_eq!(base62(alphabet[i as usize]).unwrap(), i);
         }
     }
+
+    #[test]
+    fn test_inverses() {
+        for v in u8::MIN..=u8::MAX {
+            match to_base10(v) {
+                Ok(b) => assert_eq!(base10(b).unwrap(), v),
+                Err(e) => assert_eq!(e, EncodingError::ComponentOutOfRange(v)),
+            }
+            match to_base16(v) {
+                Ok(b) => assert_eq!(base16(b).unwrap(), v),
+                Err(e) => assert_eq!(e, EncodingError::ComponentOutOfRange(v)),
+            }
+            match to_base62(v) {
+                Ok(b) => assert_eq!(base62(b).unwrap(), v),
+                Err(e) => assert_eq!(e, EncodingError::ComponentOutOfRange(v)),
+            }
+            match to_two_base10(v) {
+                Ok([b1, b2]) => assert_eq!(base10(b1).unwrap() * 10 + base10(b2).unwrap(), v),
+                Err(e) => assert_eq!(e, EncodingError::ComponentOutOfRange(v)),
+            }
+        }
+    }
 }
@@ -0,0 +1,69 @@
+#![cfg(feature = "cli")]
+
+use pretty_assertions::assert_eq;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run(args: &[&str], stdin: &[u8]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tdyne-peer-id"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cli_args_test() {
+    let output = run(
+        &[
+            "-TR4040-xxxxxxxxxxxx",
+            "%2DqB4630%2Dxxxxxxxxxxxx",
+            "2d5554333535422d787878787878787878787878",
+            "LVRSNDA0MC0AAWQ3eGtxcTA0bv8=",
+            "nonsense",
+        ],
+        b"",
+    );
+    assert_eq!(
+        output,
+        "-TR4040-xxxxxxxxxxxx: Transmission 4.0.4 [Azureus]\n\
+         %2DqB4630%2Dxxxxxxxxxxxx: qBittorrent 4.6.3 [Azureus]\n\
+         2d5554333535422d787878787878787878787878: µTorrent 3.5.5 (Beta) [Azureus]\n\
+         LVRSNDA0MC0AAWQ3eGtxcTA0bv8=: Transmission 4.0.4 [Azureus]\n\
+         nonsense: error: Can't guess the encoding\n"
+    );
+}
+
+#[test]
+fn cli_csv_test() {
+    let output = run(
+        &["--format", "csv"],
+        b"-UT355B-xxxxxxxxxxxx\r\n\n-ZZ1234-xxxxxxxxxxxx\n-TR4040-\xff\xffxxxxxxxxxx",
+    );
+    assert_eq!(
+        output,
+        "input,peer_id,client,client_id,version,style,version_error,error\n\
+         -UT355B-xxxxxxxxxxxx,2d5554333535422d787878787878787878787878,µTorrent,utorrent,3.5.5 (Beta),Azureus,,\n\
+         -ZZ1234-xxxxxxxxxxxx,2d5a5a313233342d787878787878787878787878,,,,,,\"\"\"ZZ\"\" doesn't match a known Azureus-style prefix\"\n\
+         -TR4040-\u{fffd}\u{fffd}xxxxxxxxxx,2d5452343034302dffff78787878787878787878,Transmission,transmission,4.0.4,Azureus,,\n"
+    );
+}
+
+#[test]
+fn cli_json_test() {
+    let output = run(
+        &["-f", "json", "-e", "hex"],
+        b"2d5452343034302d787878787878787878787878\n",
+    );
+    assert_eq!(
+        output,
+        r#"{"client":"Transmission","client_id":"transmission","error":null,"input":"2d5452343034302d787878787878787878787878","peer_id":"2d5452343034302d787878787878787878787878","style":"Azureus","version":"4.0.4","version_error":null}"#
+            .to_string()
+            + "\n"
+    );
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "std")]
+
+use pretty_assertions::assert_eq;
+use tdyne_peer_id_registry::errors::EncodingError;
+use tdyne_peer_id_registry::version::{
+    AlphaBetaSuffix, FourBase16, MainlineVersion, OneBase10Unknown, ShadowVersion, ThreeBase16,
+    ThreeBase16AlphaBeta, Transmission, TransmissionSuffix, TwoBase16, TwoMajTwoMinBase10, Version,
+};
+use tdyne_peer_id_registry::{encode, parse, KnownClient};
+use test_case::test_case;
+
+const RANDOM: [u8; 20] = *b"0123456789abcdefghij";
+
+#[test_case(KnownClient::Transmission, Transmission::Current(4, 0, 4, Some(TransmissionSuffix::Dev)).into(), b"-TR404Z-89abcdefghij", "Transmission", "4.0.4 (Dev)")]
+#[test_case(KnownClient::Transmission, Transmission::Pre3(2, 94, None).into(), b"-TR2940-89abcdefghij", "Transmission", "2.94")]
+#[test_case(KnownClient::QBittorrent, ThreeBase16(4, 6, 3).into(), b"-qB4630-89abcdefghij", "qBittorrent", "4.6.3")]
+#[test_case(KnownClient::UTorrent, ThreeBase16AlphaBeta(3, 5, 5, Some(AlphaBetaSuffix::Beta)).into(), b"-UT355B-89abcdefghij", "\u{00B5}Torrent", "3.5.5 (Beta)")]
+#[test_case(KnownClient::Vuze, FourBase16(5, 7, 7, 0).into(), b"-AZ5770-89abcdefghij", "Vuze", "5.7.7.0")]
+#[test_case(KnownClient::WebTorrent, TwoMajTwoMinBase10(1, 25).into(), b"-WW0125-89abcdefghij", "WebTorrent", "1.25")]
+#[test_case(KnownClient::Folx, OneBase10Unknown(5).into(), b"-FL5000-89abcdefghij", "folx", "5.x")]
+#[test_case(KnownClient::FreeDownloadManager, TwoBase16(5, 1).into(), b"-FD5100-89abcdefghij", "Free Download Manager", "5.1")]
+#[test_case(KnownClient::BiglyBtAndroid, FourBase16(3, 4, 0, 1).into(), b"-BI3401-A9abcdefghij", "BiglyBT for Android", "3.4.0.1")]
+#[test_case(KnownClient::Ares, ThreeBase16(2, 0, 5).into(), b"-AG2050-89abcdefghij", "Ares", "2.0.5")]
+#[test_case(KnownClient::Ares, FourBase16(2, 1, 7, 1).into(), b"-AN2171-89abcdefghij", "Ares", "2.1.7.1")]
+#[test_case(KnownClient::BitTornado, ShadowVersion::new(0, [Some(3), Some(18), None]).into(), b"T03I--6789abcdefghij", "BitTornado", "0.3.18")]
+#[test_case(KnownClient::Mainline, MainlineVersion::new(5, 0, 7).into(), b"M5-0-7--89abcdefghij", "Mainline", "5.0.7")]
+#[test_case(KnownClient::AmazonAWSS3, MainlineVersion::new(1, 0, 0).into(), b"S3-1-0-0-9abcdefghij", "Amazon AWS S3", "1.0.0")]
+fn encode_test(
+    client: KnownClient,
+    version: Version,
+    peer_id_bytes: &[u8; 20],
+    client_name: &str,
+    version_str: &str,
+) {
+    let peer_id = encode(client, Some(version), RANDOM).unwrap();
+    assert_eq!(&peer_id.0, peer_id_bytes);
+
+    let parsed = parse(peer_id).unwrap();
+    assert_eq!(parsed.client, client_name);
+    assert_eq!(parsed.version.unwrap().unwrap(), version_str);
+}
+
+#[test]
+fn encode_shadow_without_version() {
+    let peer_id = encode(KnownClient::Abc, None, RANDOM).unwrap();
+    assert_eq!(&peer_id.0, b"A-----6789abcdefghij");
+
+    let parsed = parse(peer_id).unwrap();
+    assert_eq!(parsed.client, "ABC");
+    assert_eq!(parsed.version, Ok(None));
+}
+
+#[test_case(KnownClient::Abc, Some(FourBase16(1, 0, 0, 0).into()), EncodingError::MismatchedVersionStyle)]
+#[test_case(KnownClient::Transmission, Some(ThreeBase16(4, 0, 4).into()), EncodingError::MismatchedVersionStyle)]
+#[test_case(KnownClient::Transmission, None, EncodingError::MismatchedVersionStyle)]
+#[test_case(KnownClient::Transmission, Some(Transmission::Current(2, 94, 0, None).into()), EncodingError::UnrepresentableVersion)]
+#[test_case(KnownClient::QBittorrent, Some(ThreeBase16(4, 16, 3).into()), EncodingError::ComponentOutOfRange(16))]
+#[test_case(KnownClient::Ares, Some(FourBase16(2, 1, 7, 16).into()), EncodingError::ComponentOutOfRange(16))]
+#[test_case(KnownClient::BitComet, Some(ThreeBase16(1, 2, 3).into()), EncodingError::MismatchedVersionStyle)]
+#[test_case(KnownClient::BitBuddy, Some(FourBase16(1, 0, 0, 0).into()), EncodingError::UnsupportedClient)]
+#[test_case(KnownClient::Tixati, None, EncodingError::UnsupportedClient)]
+#[test_case(KnownClient::PossibleBitSpirit, None, EncodingError::UnsupportedClient)]
+fn encode_error_test(client: KnownClient, version: Option<Version>, error: EncodingError) {
+    assert_eq!(encode(client, version, RANDOM).unwrap_err(), error);
+}
+
+#[test_case(*b"xxxxxxxxxxxxxxxxUDP0")]
+#[test_case(*b"xxxxxxxxxxxxxxHTTPBT")]
+fn encode_conflicting_random_test(random: [u8; 20]) {
+    assert_eq!(
+        encode(
+            KnownClient::QBittorrent,
+            Some(ThreeBase16(4, 6, 3).into()),
+            random
+        )
+        .unwrap_err(),
+        EncodingError::ConflictingRandomBytes
+    );
+}
+
+#[test]
+fn encode_biglybt_random_android_marker() {
+    let mut random = RANDOM;
+    random[8] = b'A';
+    assert_eq!(
+        encode(
+            KnownClient::BiglyBt,
+            Some(FourBase16(3, 4, 0, 1).into()),
+            random
+        )
+        .unwrap_err(),
+        EncodingError::ConflictingRandomBytes
+    );
+}
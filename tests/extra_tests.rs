@@ -1,3 +1,5 @@
+#![cfg(feature = "std")]
+
 use pretty_assertions::assert_eq;
 use tdyne_peer_id::PeerId;
 use test_case::test_case;
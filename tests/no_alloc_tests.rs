@@ -0,0 +1,89 @@
+// the structured API promises not to allocate, this checks it with a counting allocator.
+// The counter is global, so everything allocation-sensitive lives in a single test.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tdyne_peer_id_registry::{encode_bytes, parse_structured_bytes, KnownClient};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// writes into a fixed buffer, so that formatting itself doesn't allocate
+struct Buffer {
+    bytes: [u8; 256],
+    len: usize,
+}
+
+impl Write for Buffer {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let end = self.len + s.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(std::fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+const PEER_IDS: &[&[u8; 20]] = &[
+    b"-TR404Z-*\x00\x01d7xkqq04n",
+    b"-UT355B-xxxxxxxxxxxx",
+    b"-qBx630-xxxxxxxxxxxx",
+    b"-Z\xff1234-xxxxxxxxxxxx",
+    b"T03I--008gY6iB6Aq27C",
+    b"M4-4-0--xxxxxxxxxxxx",
+    b"Q1-2-3--xxxxxxxxxxxx",
+    b"exbc\x00\x21LORDxxxxxxxxxx",
+    b"\x003BS\x07\x24\x88\x96D\xc5\x950\x8a_\xf2\xcaUDP0",
+    b"Pando-xxxxxxxxxxxxxx",
+    b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+];
+
+#[test]
+fn no_alloc_test() {
+    let mut buffer = Buffer {
+        bytes: [0; 256],
+        len: 0,
+    };
+
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+
+    for peer_id in PEER_IDS {
+        buffer.len = 0;
+        match parse_structured_bytes(**peer_id) {
+            Ok(parsed) => {
+                write!(buffer, "{} {}", parsed.client, parsed.style).unwrap();
+                match parsed.version {
+                    Ok(Some(v)) => write!(buffer, "{v}").unwrap(),
+                    Ok(None) => (),
+                    Err(e) => write!(buffer, "{e}").unwrap(),
+                }
+            }
+            Err(e) => write!(buffer, "{e}").unwrap(),
+        }
+    }
+
+    let encoded = encode_bytes(KnownClient::Transmission, None, [b'x'; 20]);
+    if let Err(e) = encoded {
+        write!(buffer, "{e}").unwrap();
+    }
+
+    assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), before);
+}
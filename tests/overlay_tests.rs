@@ -0,0 +1,176 @@
+#![cfg(feature = "overlay")]
+
+use pretty_assertions::assert_eq;
+use tdyne_peer_id::PeerId;
+use tdyne_peer_id_registry::errors::{OverlayError, RegistryError};
+use tdyne_peer_id_registry::version::{FourBase16, ThreeBase16, Version};
+use tdyne_peer_id_registry::{RegisteredClient, Registry, Style};
+use test_case::test_case;
+
+const TOML: &str = r#"
+[[azureus]]
+tag = "XY"
+name = "In-house"
+style = "ThreeBase16"
+
+[[azureus]]
+tag = "TR"
+name = "Transmission fork"
+style = "FourBase16"
+
+[[shadow]]
+tag = "Z"
+name = "Zed"
+
+[[substring]]
+substring = "FOOBAR"
+offset = 2
+name = "Foo"
+version = "unknown"
+"#;
+
+const JSON: &str = r#"{
+    "azureus": [
+        {"tag": "XY", "name": "In-house", "style": "ThreeBase16"},
+        {"tag": "TR", "name": "Transmission fork", "style": "FourBase16"}
+    ],
+    "shadow": [{"tag": "Z", "name": "Zed"}],
+    "substring": [{"substring": "FOOBAR", "offset": 2, "name": "Foo", "version": "unknown"}]
+}"#;
+
+fn check(registry: &Registry) {
+    let parsed = registry
+        .parse_structured(PeerId::from(b"-XY1230-xxxxxxxxxxxx"))
+        .unwrap();
+    assert_eq!(parsed.client, RegisteredClient::Custom("In-house"));
+    assert_eq!(parsed.version, Ok(Some(ThreeBase16(1, 2, 3).into())));
+
+    let parsed = registry
+        .parse_structured(PeerId::from(b"-TR4041-xxxxxxxxxxxx"))
+        .unwrap();
+    assert_eq!(parsed.client, RegisteredClient::Custom("Transmission fork"));
+    assert_eq!(parsed.version, Ok(Some(FourBase16(4, 0, 4, 1).into())));
+
+    let parsed = registry
+        .parse_structured(PeerId::from(b"Z1----xxxxxxxxxxxxxx"))
+        .unwrap();
+    assert_eq!(parsed.client, RegisteredClient::Custom("Zed"));
+    assert_eq!(parsed.style, Style::Shadow);
+
+    let parsed = registry
+        .parse_structured(PeerId::from(b"xxFOOBARxxxxxxxxxxxx"))
+        .unwrap();
+    assert_eq!(parsed.client, RegisteredClient::Custom("Foo"));
+    assert_eq!(parsed.version, Ok(Some(Version::Unknown)));
+}
+
+#[test]
+fn overlay_toml_test() {
+    let registry = Registry::builder()
+        .overlay_toml(TOML)
+        .unwrap()
+        .build()
+        .unwrap();
+    check(&registry);
+}
+
+#[test]
+fn overlay_json_test() {
+    let registry = Registry::builder()
+        .overlay_json(JSON)
+        .unwrap()
+        .build()
+        .unwrap();
+    check(&registry);
+}
+
+#[test]
+fn overlay_file_test() {
+    let dir = std::env::temp_dir().join(format!("overlay_file_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("clients.toml"), TOML).unwrap();
+    std::fs::write(dir.join("clients.json"), JSON).unwrap();
+    std::fs::write(dir.join("clients.yaml"), "").unwrap();
+
+    for name in ["clients.toml", "clients.json"] {
+        let registry = Registry::builder()
+            .overlay_file(dir.join(name))
+            .unwrap()
+            .build()
+            .unwrap();
+        check(&registry);
+    }
+
+    assert_eq!(
+        Registry::builder()
+            .overlay_file(dir.join("clients.yaml"))
+            .unwrap_err(),
+        OverlayError::UnsupportedFormat
+    );
+    assert_eq!(
+        Registry::builder()
+            .overlay_file(dir.join("missing.toml"))
+            .unwrap_err(),
+        OverlayError::Io(std::io::ErrorKind::NotFound)
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test_case(
+    "[[azureus]]\ntag = \"XY\"\nname = \"A\"\nstyle = \"ThreeBase10\"",
+    "azureus[0]: \"ThreeBase10\" is not a known version style"
+)]
+#[test_case(
+    "[[azureus]]\ntag = \"XYZ\"\nname = \"A\"\nstyle = \"ThreeBase16\"",
+    "azureus[0]: \"XYZ\" has the wrong length for a tag"
+)]
+#[test_case(
+    "[[shadow]]\ntag = \"Z\"\nname = \"A\"\n[[shadow]]\ntag = \"Z\"\nname = \"B\"",
+    "shadow[1]: Shadow-style tag \"Z\" is registered twice"
+)]
+#[test_case(
+    "[[substring]]\nsubstring = \"FOOBAR\"\noffset = 18\nname = \"A\"",
+    "substring[0]: A substring of length 6 at offset 18 doesn't fit into a peer ID"
+)]
+fn overlay_error_test(overlay: &str, message: &str) {
+    let error = Registry::builder().overlay_toml(overlay).unwrap_err();
+    assert_eq!(error.to_string(), message);
+}
+
+// a huge offset from an operator-supplied file must be an error, not an overflow
+#[test]
+fn overlay_huge_offset_test() {
+    let error = Registry::builder()
+        .overlay_json(
+            r#"{"substring": [{"substring": "AB", "offset": 18446744073709551615, "name": "A"}]}"#,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        OverlayError::Registry(entry, RegistryError::SubstringOutOfBounds(offset, 2))
+            if entry.index == 0 && offset == usize::MAX
+    ));
+}
+
+#[test]
+fn overlay_duplicate_test() {
+    let error = Registry::builder()
+        .overlay_json(r#"{"azureus": [{"tag": "XY", "name": "A", "style": "NoVersion"}]}"#)
+        .unwrap()
+        .overlay_json(r#"{"azureus": [{"tag": "XY", "name": "B", "style": "NoVersion"}]}"#)
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        OverlayError::Registry(entry, RegistryError::DuplicateAzureusTag(tag))
+            if entry.index == 0 && &tag == b"XY"
+    ));
+}
+
+#[test_case("[[azureus]]\ntag = \"XY\"\nname = \"A\"\nstyle = \"ThreeBase16\"\nstlye = 1")]
+#[test_case("[[substring]]\nsubstring = \"A\"\nname = \"A\"\nversion = \"fixed\"")]
+#[test_case("[[client]]\nname = \"A\"")]
+fn overlay_syntax_test(overlay: &str) {
+    let error = Registry::builder().overlay_toml(overlay).unwrap_err();
+    assert!(matches!(error, OverlayError::Syntax(_)), "{error:?}");
+}
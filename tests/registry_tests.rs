@@ -0,0 +1,160 @@
+#![cfg(feature = "std")]
+
+use pretty_assertions::assert_eq;
+use tdyne_peer_id::PeerId;
+use tdyne_peer_id_registry::errors::{ClientParsingError, RegistryError};
+use tdyne_peer_id_registry::version::{
+    AlphaBetaSuffix, AzureusVersionStyle, FourBase16, ShadowVersion, ThreeBase16AlphaBeta,
+    Transmission, Version,
+};
+use tdyne_peer_id_registry::{KnownClient, RegisteredClient, Registry, Style};
+use test_case::test_case;
+
+fn registry() -> Registry {
+    Registry::builder()
+        .azureus(*b"XY", "In-house", AzureusVersionStyle::FourBase16)
+        // overrides Transmission
+        .azureus(
+            *b"TR",
+            "Transmission fork",
+            AzureusVersionStyle::Transmission,
+        )
+        .shadow(b'Z', "Zed")
+        .substring(b"FOOBAR", 0, "Foo", None)
+        // overrides Opera's `OP` substring
+        .substring(b"OPX", 0, "Not Opera", Some(Version::Unknown))
+        .build()
+        .unwrap()
+}
+
+#[test_case(
+    b"-XY1234-xxxxxxxxxxxx",
+    RegisteredClient::Custom("In-house"),
+    Style::Azureus,
+    Some(FourBase16(1, 2, 3, 4).into())
+)]
+#[test_case(
+    b"-TR4040-xxxxxxxxxxxx",
+    RegisteredClient::Custom("Transmission fork"),
+    Style::Azureus,
+    Some(Transmission::Current(4, 0, 4, None).into())
+)]
+#[test_case(
+    b"Z12---xxxxxxxxxxxxxx",
+    RegisteredClient::Custom("Zed"),
+    Style::Shadow,
+    Some(ShadowVersion::new(1, [Some(2), None, None]).into())
+)]
+#[test_case(
+    b"FOOBARxxxxxxxxxxxxxx",
+    RegisteredClient::Custom("Foo"),
+    Style::Substring,
+    None
+)]
+#[test_case(
+    b"OPXxxxxxxxxxxxxxxxxx",
+    RegisteredClient::Custom("Not Opera"),
+    Style::Substring,
+    Some(Version::Unknown)
+)]
+#[test_case(
+    b"OPxxxxxxxxxxxxxxxxxx",
+    RegisteredClient::Known(KnownClient::Opera),
+    Style::Substring,
+    Some(Version::Unknown)
+)]
+#[test_case(
+    b"-UT355B-xxxxxxxxxxxx",
+    RegisteredClient::Known(KnownClient::UTorrent),
+    Style::Azureus,
+    Some(ThreeBase16AlphaBeta(
+        3,
+        5,
+        5,
+        Some(AlphaBetaSuffix::Beta)
+    )
+    .into())
+)]
+// custom Shad0w tags don't apply to spoof mode peer IDs
+#[test_case(
+    b"Z12---xxxxxxxxxxUDP0",
+    RegisteredClient::Known(KnownClient::PossibleBitSpirit),
+    Style::SpoofMarker,
+    None
+)]
+fn registry_test(
+    peer_id_bytes: &[u8; 20],
+    client: RegisteredClient,
+    style: Style,
+    version: Option<Version>,
+) {
+    let registry = registry();
+    let parsed = registry
+        .parse_structured(PeerId::from(peer_id_bytes))
+        .unwrap();
+    assert_eq!(parsed.client, client);
+    assert_eq!(parsed.style, style);
+    assert_eq!(parsed.version, Ok(version));
+}
+
+#[test]
+fn registry_parse_test() {
+    let parsed = registry()
+        .parse(PeerId::from(b"-XY1234-xxxxxxxxxxxx"))
+        .unwrap();
+    assert_eq!(parsed.client, "In-house");
+    assert_eq!(parsed.version, Ok(Some("1.2.3.4".to_string())));
+}
+
+#[test]
+fn registry_unknown_test() {
+    let registry = Registry::builder().build().unwrap();
+    assert_eq!(
+        registry.parse_structured(PeerId::from(b"-XY1234-xxxxxxxxxxxx")),
+        Err(ClientParsingError::UnknownAzureusStylePrefix(*b"XY"))
+    );
+}
+
+#[test]
+fn registry_substring_after_structured_test() {
+    // `-ZZ1234-` is Azureus-style, so the substring rule doesn't apply to it
+    let registry = Registry::builder()
+        .substring(b"-ZZ", 0, "Zz", None)
+        .build()
+        .unwrap();
+    assert_eq!(
+        registry.parse_structured(PeerId::from(b"-ZZ1234-xxxxxxxxxxxx")),
+        Err(ClientParsingError::UnknownAzureusStylePrefix(*b"ZZ"))
+    );
+}
+
+#[test]
+fn registry_errors_test() {
+    let duplicate = Registry::builder()
+        .azureus(*b"XY", "One", AzureusVersionStyle::NoVersion)
+        .azureus(*b"XY", "Two", AzureusVersionStyle::NoVersion)
+        .build();
+    assert_eq!(
+        duplicate.unwrap_err(),
+        RegistryError::DuplicateAzureusTag(*b"XY")
+    );
+
+    let dash = Registry::builder().shadow(b'-', "Dash").build();
+    assert_eq!(dash.unwrap_err(), RegistryError::InvalidShadowTag(b'-'));
+
+    let too_long = Registry::builder()
+        .substring(b"abcdef", 15, "Long", None)
+        .build();
+    assert_eq!(
+        too_long.unwrap_err(),
+        RegistryError::SubstringOutOfBounds(15, 6)
+    );
+
+    let huge_offset = Registry::builder()
+        .substring(b"AB", usize::MAX, "Huge", None)
+        .build();
+    assert_eq!(
+        huge_offset.unwrap_err(),
+        RegistryError::SubstringOutOfBounds(usize::MAX, 2)
+    );
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "serde")]
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tdyne_peer_id::PeerId;
+use tdyne_peer_id_registry::errors::{ClientParsingError, VersionParsingError};
+use tdyne_peer_id_registry::{parse, parse_structured, KnownClient, Parsed};
+use test_case::test_case;
+
+#[test_case(
+    b"-TR4040-xxxxxxxxxxxx",
+    json!({"Ok": {"client": "transmission", "style": "Azureus", "version": {"Ok":
+        {"Azureus": {"Transmission": {"Current": [4, 0, 4, null]}}}}}})
+)]
+#[test_case(
+    b"T03I--008gY6iB6Aq27C",
+    json!({"Ok": {"client": "bittornado", "style": "Shadow", "version": {"Ok":
+        {"Shadow": [0, [3, 18, null]]}}}})
+)]
+#[test_case(
+    b"-qBx630-xxxxxxxxxxxx",
+    json!({"Ok": {"client": "qbittorrent", "style": "Azureus", "version": {"Err":
+        {"DigitNotBase16": {"ascii": "x", "hex": "78"}}}}})
+)]
+#[test_case(
+    b"-Z\xff1234-xxxxxxxxxxxx",
+    json!({"Err": {"UnknownAzureusStylePrefix": {"ascii": "Z\\xff", "hex": "5aff"}}})
+)]
+fn structured_test(peer_id_bytes: &[u8; 20], expected: serde_json::Value) {
+    let parsed = parse_structured(PeerId::from(peer_id_bytes));
+    assert_eq!(serde_json::to_value(parsed).unwrap(), expected);
+}
+
+#[test_case(b"-TR4040-xxxxxxxxxxxx")]
+#[test_case(b"-qBx630-xxxxxxxxxxxx")]
+#[test_case(b"Pando-xxxxxxxxxxxxxx")]
+fn parsed_round_trip_test(peer_id_bytes: &[u8; 20]) {
+    let parsed = parse(PeerId::from(peer_id_bytes)).unwrap();
+    let json = serde_json::to_string(&parsed).unwrap();
+    let back: Parsed = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.client, parsed.client);
+    assert_eq!(back.version, parsed.version);
+}
+
+#[test_case(ClientParsingError::UnknownAzureusStylePrefix(*b"Z\xff"))]
+#[test_case(ClientParsingError::UnknownShadowStylePrefix(b'\n'))]
+#[test_case(ClientParsingError::UnknownMainlineClientPrefix(*b"Q1-2"))]
+#[test_case(ClientParsingError::UnknownClient)]
+fn client_error_round_trip_test(error: ClientParsingError) {
+    let json = serde_json::to_string(&error).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ClientParsingError>(&json).unwrap(),
+        error
+    );
+}
+
+#[test]
+fn version_error_test() {
+    let error = VersionParsingError::UnexpectedBitCometBitLordHeader(b'e', b'x', b'b', 0);
+    let value = serde_json::to_value(error).unwrap();
+    assert_eq!(
+        value,
+        json!({"UnexpectedBitCometBitLordHeader": [
+            {"ascii": "e", "hex": "65"},
+            {"ascii": "x", "hex": "78"},
+            {"ascii": "b", "hex": "62"},
+            {"ascii": "\\x00", "hex": "00"},
+        ]})
+    );
+    assert_eq!(
+        serde_json::from_value::<VersionParsingError>(value).unwrap(),
+        error
+    );
+}
+
+#[test]
+fn bad_bytes_test() {
+    for value in [
+        json!({"UnknownShadowStylePrefix": {"ascii": "", "hex": "5a5a"}}),
+        json!({"UnknownShadowStylePrefix": {"ascii": "", "hex": "zz"}}),
+    ] {
+        assert!(serde_json::from_value::<ClientParsingError>(value).is_err());
+    }
+}
+
+#[test]
+fn known_client_test() {
+    assert_eq!(
+        serde_json::to_string(&KnownClient::UTorrent).unwrap(),
+        r#""utorrent""#
+    );
+    assert_eq!(
+        serde_json::from_str::<KnownClient>(r#""xunlei""#).unwrap(),
+        KnownClient::Xunlei
+    );
+    assert!(serde_json::from_str::<KnownClient>(r#""UTorrent""#).is_err());
+}
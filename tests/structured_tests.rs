@@ -0,0 +1,60 @@
+#![cfg(feature = "std")]
+
+use pretty_assertions::assert_eq;
+use tdyne_peer_id::PeerId;
+use tdyne_peer_id_registry::version::{
+    BitCometMod, FourBase16, MainlineVersion, NumericVersion, PreRelease, Transmission,
+    TransmissionSuffix, Version,
+};
+use tdyne_peer_id_registry::{parse_structured, KnownClient, Style};
+use test_case::test_case;
+
+#[test_case(b"-TR404Z-*\x00\x01d7xkqq04n", KnownClient::Transmission, Style::Azureus, Some(Transmission::Current(4, 0, 4, Some(TransmissionSuffix::Dev)).into()))]
+#[test_case(b"-BI5701-Axxxxxxxxxxx", KnownClient::BiglyBtAndroid, Style::Azureus, Some(FourBase16(5, 7, 0, 1).into()))]
+#[test_case(b"-BF0000-xxxxxxxxxxxx", KnownClient::BitFlu, Style::Azureus, None)]
+#[test_case(
+    b"-BB0000-xxxxxxxxxxxx",
+    KnownClient::BitBuddy,
+    Style::Azureus,
+    Some(Version::Unknown)
+)]
+#[test_case(b"A--------YMyoBPXYy2L", KnownClient::Abc, Style::Shadow, None)]
+#[test_case(b"M5-0-7--9aa757efd5be", KnownClient::Mainline, Style::Mainline, Some(MainlineVersion::new(5, 0, 7).into()))]
+#[test_case(
+    b"-UT170-xxxxxxxxxxxxx",
+    KnownClient::UTorrent,
+    Style::Substring,
+    Some(Version::Fixed(
+        "1.7.0 RC",
+        NumericVersion::try_new(&[1, 7, 0], Some(PreRelease::ReleaseCandidate))
+    ))
+)]
+#[test_case(
+    b"FUTB710xxxxxxxxxxxxx",
+    KnownClient::BitComet,
+    Style::BitComet,
+    Some(Version::BitCometBitLord(7, 10, Some(BitCometMod::Solidox)))
+)]
+#[test_case(
+    b"\x003BS\x07\x24\x88\x96D\xc5\x950\x8a_\xf2\xcaUDP0",
+    KnownClient::BitSpirit,
+    Style::BitSpirit,
+    Some(Version::BitSpirit(3))
+)]
+#[test_case(
+    b"M4-0-2--xxxxxxxxUDP0",
+    KnownClient::PossibleBitSpirit,
+    Style::SpoofMarker,
+    None
+)]
+fn structured_test(
+    peer_id_bytes: &[u8; 20],
+    client: KnownClient,
+    style: Style,
+    version: Option<Version>,
+) {
+    let parsed = parse_structured(PeerId::from(peer_id_bytes)).unwrap();
+    assert_eq!(parsed.client, client);
+    assert_eq!(parsed.style, style);
+    assert_eq!(parsed.version, Ok(version));
+}
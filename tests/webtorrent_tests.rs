@@ -1,3 +1,5 @@
+#![cfg(feature = "std")]
+
 use pretty_assertions::assert_eq;
 use tdyne_peer_id::PeerId;
 use tdyne_peer_id_registry::errors::ClientParsingError;